@@ -0,0 +1,200 @@
+use amm::Config;
+use amm_client::{
+    config_pda, decode_config, instructions, position_pda, PoolKeys, PoolState,
+};
+use anchor_client::{
+    solana_account_decoder::UiAccountEncoding,
+    solana_client::{
+        rpc_client::RpcClient,
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp, RpcFilterType},
+    },
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::Transaction,
+    },
+};
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_spl::token::{Mint, TokenAccount};
+use anyhow::{anyhow, Context as _, Result};
+use serde_json::{json, Value};
+
+pub struct Context {
+    rpc: RpcClient,
+    payer: Keypair,
+}
+
+impl Context {
+    pub fn new(url: &str, keypair: &str) -> Result<Self> {
+        let path = match keypair.strip_prefix("~/") {
+            Some(rest) => format!("{}/{}", std::env::var("HOME")?, rest),
+            None => keypair.to_string(),
+        };
+        let payer = read_keypair_file(&path).map_err(|e| anyhow!("reading keypair {}: {}", path, e))?;
+
+        Ok(Self {
+            rpc: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+            payer,
+        })
+    }
+
+    fn send(&self, ix: Instruction) -> Result<Value> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&self.payer.pubkey()), &[&self.payer], blockhash);
+        let signature = self.rpc.send_and_confirm_transaction(&tx)?;
+
+        Ok(json!({ "signature": signature.to_string() }))
+    }
+
+    fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let data = self.rpc.get_account_data(address).with_context(|| format!("fetching {}", address))?;
+        Ok(T::try_deserialize(&mut &data[..])?)
+    }
+
+    fn pool(&self, seed: u64) -> Result<(PoolKeys, Config)> {
+        let (address, _) = config_pda(seed);
+        let data = self.rpc.get_account_data(&address).with_context(|| format!("no pool with seed {}", seed))?;
+        let config = decode_config(&data)?;
+
+        Ok((PoolKeys::from_config(&config), config))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_pool(
+        &self,
+        seed: u64,
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        fee: u16,
+        authority: Option<Pubkey>,
+        emergency_delay: i64,
+        lp_decimals: Option<u8>,
+        metadata_x: bool,
+        metadata_y: bool,
+        initial_x: u64,
+        initial_y: u64,
+    ) -> Result<Value> {
+        let pool = PoolKeys::new(seed, mint_x, mint_y);
+        let ix = instructions::initialize_with_liquidity(
+            &self.payer.pubkey(),
+            &pool,
+            fee,
+            authority,
+            emergency_delay,
+            lp_decimals,
+            metadata_x,
+            metadata_y,
+            initial_x,
+            initial_y,
+        );
+
+        let mut output = self.send(ix)?;
+        output["config"] = json!(pool.config.to_string());
+        output["mint_lp"] = json!(pool.mint_lp.to_string());
+        Ok(output)
+    }
+
+    // pools with a hook program get no extra accounts from the cli, hooks that need some have to be driven through amm-client
+    pub fn deposit(&self, seed: u64, amount: u64, max_x: u64, max_y: u64, expiration: Option<i64>, wrap_sol: bool) -> Result<Value> {
+        let (pool, _) = self.pool(seed)?;
+        self.send(instructions::deposit(&self.payer.pubkey(), &pool, amount, max_x, max_y, expiration, wrap_sol, &[]))
+    }
+
+    pub fn withdraw(&self, seed: u64, amount: u64, min_x: u64, min_y: u64, expiration: Option<i64>, wrap_sol: bool) -> Result<Value> {
+        let (pool, _) = self.pool(seed)?;
+        let user = self.payer.pubkey();
+        let with_position = self.rpc.get_account(&position_pda(&pool.config, &user).0).is_ok();
+
+        self.send(instructions::withdraw(&user, &pool, amount, min_x, min_y, expiration, wrap_sol, with_position, &[]))
+    }
+
+    pub fn swap(&self, seed: u64, amount: u64, is_x: bool, min: u64, expiration: Option<i64>, wrap_sol: bool) -> Result<Value> {
+        let (pool, _) = self.pool(seed)?;
+        self.send(instructions::swap(&self.payer.pubkey(), &pool, amount, is_x, min, expiration, wrap_sol, &[]))
+    }
+
+    pub fn lock(&self, seed: u64, lock: bool) -> Result<Value> {
+        let (pool, _) = self.pool(seed)?;
+        let ix = match lock {
+            true => instructions::lock(&self.payer.pubkey(), &pool),
+            false => instructions::unlock(&self.payer.pubkey(), &pool),
+        };
+        self.send(ix)
+    }
+
+    pub fn show_pool(&self, seed: u64) -> Result<Value> {
+        let (pool, config) = self.pool(seed)?;
+
+        let vault_x: TokenAccount = self.fetch(&pool.vault_x)?;
+        let vault_y: TokenAccount = self.fetch(&pool.vault_y)?;
+        let mint_x: Mint = self.fetch(&pool.mint_x)?;
+        let mint_y: Mint = self.fetch(&pool.mint_y)?;
+        let mint_lp: Mint = self.fetch(&pool.mint_lp)?;
+
+        let state = PoolState::new(&config, mint_lp.supply);
+
+        // price of one whole x in whole y, decimals taken into account
+        let price = match state.reserve_x {
+            0 => None,
+            _ => Some(
+                (state.reserve_y as f64 / 10f64.powi(mint_y.decimals as i32))
+                    / (state.reserve_x as f64 / 10f64.powi(mint_x.decimals as i32)),
+            ),
+        };
+
+        let mut output = config_json(&pool.config, &config);
+        output["mint_lp"] = json!(pool.mint_lp.to_string());
+        output["vault_x"] = json!(pool.vault_x.to_string());
+        output["vault_y"] = json!(pool.vault_y.to_string());
+        output["reserve_x"] = json!(state.reserve_x);
+        output["reserve_y"] = json!(state.reserve_y);
+        // tokens sent straight to the vaults, waiting for the authority to skim or sync them
+        output["excess_x"] = json!(vault_x.amount.saturating_sub(state.reserve_x));
+        output["excess_y"] = json!(vault_y.amount.saturating_sub(state.reserve_y));
+        output["lp_supply"] = json!(state.lp_supply);
+        output["price_x_in_y"] = json!(price);
+        Ok(output)
+    }
+
+    pub fn list_pools(&self) -> Result<Value> {
+        // every Config account is a pool, the rpc node only returns accounts starting with its discriminator
+        let filter = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, Config::DISCRIMINATOR))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let pools: Vec<Value> = self
+            .rpc
+            .get_program_accounts_with_config(&amm::ID, filter)?
+            .into_iter()
+            .filter_map(|(address, account)| {
+                decode_config(&account.data).ok().map(|config| config_json(&address, &config))
+            })
+            .collect();
+
+        Ok(json!(pools))
+    }
+}
+
+fn config_json(address: &Pubkey, config: &Config) -> Value {
+    json!({
+        "config": address.to_string(),
+        "seed": config.seed,
+        "authority": config.authority.map(|a| a.to_string()),
+        "mint_x": config.mint_x.to_string(),
+        "mint_y": config.mint_y.to_string(),
+        "fee": config.fee,
+        "locked": config.locked,
+        "locked_at": config.locked_at,
+        "emergency_delay": config.emergency_delay,
+        "permissioned": config.permissioned,
+        "hook_program": config.hook_program.map(|p| p.to_string()),
+        "lp_decimals": config.lp_decimals,
+    })
+}
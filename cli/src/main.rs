@@ -0,0 +1,163 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+mod commands;
+
+// every amount is in base units of the token, the cli never scales by decimals on the way in
+#[derive(Parser)]
+#[command(name = "amm-cli", about = "Administer and trade against amm pools")]
+struct Cli {
+    /// RPC endpoint
+    #[arg(long, short = 'u', global = true, env = "AMM_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Keypair file that signs and pays
+    #[arg(long, short = 'k', global = true, env = "AMM_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    keypair: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a new pool
+    InitPool {
+        #[arg(long)]
+        seed: u64,
+        #[arg(long)]
+        mint_x: Pubkey,
+        #[arg(long)]
+        mint_y: Pubkey,
+        /// Swap fee in basis points
+        #[arg(long)]
+        fee: u16,
+        /// Pool authority, leave out for a pool nobody controls
+        #[arg(long)]
+        authority: Option<Pubkey>,
+        /// Seconds a locked pool waits before emergency withdrawals open
+        #[arg(long, default_value_t = 0)]
+        emergency_delay: i64,
+        /// LP mint decimals, defaults to the larger of the pair's decimals
+        #[arg(long)]
+        lp_decimals: Option<u8>,
+        /// mint_x has metaplex metadata to name the LP token after
+        #[arg(long)]
+        metadata_x: bool,
+        /// mint_y has metaplex metadata to name the LP token after
+        #[arg(long)]
+        metadata_y: bool,
+        /// X to seed the pool with from your wallet, sets the initial price together with --initial-y
+        #[arg(long, default_value_t = 0)]
+        initial_x: u64,
+        /// Y to seed the pool with from your wallet
+        #[arg(long, default_value_t = 0)]
+        initial_y: u64,
+    },
+    /// Provide liquidity and mint LP tokens
+    Deposit {
+        #[arg(long)]
+        seed: u64,
+        /// LP tokens to mint
+        #[arg(long)]
+        amount: u64,
+        #[arg(long)]
+        max_x: u64,
+        #[arg(long)]
+        max_y: u64,
+        /// Unix timestamp after which the transaction is rejected
+        #[arg(long)]
+        expiration: Option<i64>,
+        /// Pay in and get paid out in SOL on pools with a wSOL side, your wSOL account is left alone
+        #[arg(long)]
+        wrap_sol: bool,
+    },
+    /// Burn LP tokens for the underlying tokens
+    Withdraw {
+        #[arg(long)]
+        seed: u64,
+        /// LP tokens to burn
+        #[arg(long)]
+        amount: u64,
+        #[arg(long, default_value_t = 0)]
+        min_x: u64,
+        #[arg(long, default_value_t = 0)]
+        min_y: u64,
+        #[arg(long)]
+        expiration: Option<i64>,
+        /// Pay in and get paid out in SOL on pools with a wSOL side, your wSOL account is left alone
+        #[arg(long)]
+        wrap_sol: bool,
+    },
+    /// Sell X for Y, or Y for X with --sell-y
+    Swap {
+        #[arg(long)]
+        seed: u64,
+        /// Amount sold
+        #[arg(long)]
+        amount: u64,
+        #[arg(long)]
+        sell_y: bool,
+        /// Minimum amount bought
+        #[arg(long)]
+        min: u64,
+        #[arg(long)]
+        expiration: Option<i64>,
+        /// Pay in and get paid out in SOL on pools with a wSOL side, your wSOL account is left alone
+        #[arg(long)]
+        wrap_sol: bool,
+    },
+    /// Lock a pool (authority only)
+    Lock {
+        #[arg(long)]
+        seed: u64,
+    },
+    /// Unlock a pool (authority only)
+    Unlock {
+        #[arg(long)]
+        seed: u64,
+    },
+    /// Decoded config, reserves, price and LP supply of a pool
+    ShowPool {
+        #[arg(long)]
+        seed: u64,
+    },
+    /// Every pool owned by the program
+    ListPools,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let ctx = commands::Context::new(&cli.url, &cli.keypair)?;
+
+    let output = match cli.command {
+        Command::InitPool {
+            seed,
+            mint_x,
+            mint_y,
+            fee,
+            authority,
+            emergency_delay,
+            lp_decimals,
+            metadata_x,
+            metadata_y,
+            initial_x,
+            initial_y,
+        } => ctx.init_pool(seed, mint_x, mint_y, fee, authority, emergency_delay, lp_decimals, metadata_x, metadata_y, initial_x, initial_y)?,
+        Command::Deposit { seed, amount, max_x, max_y, expiration, wrap_sol } => {
+            ctx.deposit(seed, amount, max_x, max_y, expiration, wrap_sol)?
+        }
+        Command::Withdraw { seed, amount, min_x, min_y, expiration, wrap_sol } => {
+            ctx.withdraw(seed, amount, min_x, min_y, expiration, wrap_sol)?
+        }
+        Command::Swap { seed, amount, sell_y, min, expiration, wrap_sol } => ctx.swap(seed, amount, !sell_y, min, expiration, wrap_sol)?,
+        Command::Lock { seed } => ctx.lock(seed, true)?,
+        Command::Unlock { seed } => ctx.lock(seed, false)?,
+        Command::ShowPool { seed } => ctx.show_pool(seed)?,
+        Command::ListPools => ctx.list_pools()?,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
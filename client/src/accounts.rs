@@ -0,0 +1,34 @@
+use anchor_lang::{AccountDeserialize, Result};
+
+use amm::{Config, Farm, LimitOrder, LpPosition, StakePosition, TwapOrder, WhitelistEntry};
+
+// decoders for raw account data as returned by getAccountInfo, discriminator included
+// they check the discriminator, so passing the wrong kind of account fails instead of decoding garbage
+
+pub fn decode_config(data: &[u8]) -> Result<Config> {
+    Config::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_whitelist_entry(data: &[u8]) -> Result<WhitelistEntry> {
+    WhitelistEntry::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_lp_position(data: &[u8]) -> Result<LpPosition> {
+    LpPosition::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_farm(data: &[u8]) -> Result<Farm> {
+    Farm::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_stake_position(data: &[u8]) -> Result<StakePosition> {
+    StakePosition::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_limit_order(data: &[u8]) -> Result<LimitOrder> {
+    LimitOrder::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_twap_order(data: &[u8]) -> Result<TwapOrder> {
+    TwapOrder::try_deserialize(&mut &data[..])
+}
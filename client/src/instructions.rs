@@ -0,0 +1,851 @@
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::{instruction::Instruction, sysvar},
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token,
+    metadata::mpl_token_metadata,
+    token::{self, spl_token::native_mint},
+};
+
+use amm::ID;
+
+use crate::pda::{
+    ata, limit_order_pda, metadata_pda, position_pda, stake_pda, twap_order_pda, whitelist_pda, wsol_pda, PoolKeys,
+};
+
+// typed builders for the amm instructions, account lists come straight from the program's
+// generated amm::accounts structs so they can't drift from the on-chain layout
+//
+// deposit, swap and withdraw take wrap_sol: on pools with a wSOL side, pay in and get paid out in SOL
+// through a temporary account at wsol_pda, the user's own wSOL account is left alone
+//
+// deposit, swap, withdraw and claim_fees take hook_accounts: the extra accounts the pool's hook program expects,
+// they are appended as remaining accounts and forwarded to the hook unchanged
+
+fn instruction(accounts: Vec<AccountMeta>, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: data.data(),
+    }
+}
+
+fn whitelist_entry(pool: &PoolKeys, user: &Pubkey) -> Option<Pubkey> {
+    pool.permissioned.then(|| whitelist_pda(&pool.config, user).0)
+}
+
+fn wsol(user: &Pubkey, wrap_sol: bool) -> Option<Pubkey> {
+    wrap_sol.then(|| wsol_pda(user).0)
+}
+
+// the user's ata of mint, left out when a source or recipient replaces it, or the temporary wSOL account does
+fn user_ata(user: &Pubkey, mint: &Pubkey, replaced: bool, wrap_sol: bool) -> Option<Pubkey> {
+    let wrapped = wrap_sol && *mint == native_mint::ID;
+    (!replaced && !wrapped).then(|| ata(user, mint))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn initialize(
+    initializer: &Pubkey,
+    pool: &PoolKeys,
+    fee: u16,
+    authority: Option<Pubkey>,
+    emergency_delay: i64,
+    lp_decimals: Option<u8>,
+    metadata_x: bool, // whether mint_x has metaplex metadata to name the lp token after
+    metadata_y: bool,
+) -> Instruction {
+    initialize_ix(initializer, pool, fee, authority, emergency_delay, lp_decimals, metadata_x, metadata_y, 0, 0)
+}
+
+// initialize and seed the pool with initial_x and initial_y from the initializer's atas in one go, see Initialize::seed_liquidity
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_with_liquidity(
+    initializer: &Pubkey,
+    pool: &PoolKeys,
+    fee: u16,
+    authority: Option<Pubkey>,
+    emergency_delay: i64,
+    lp_decimals: Option<u8>,
+    metadata_x: bool,
+    metadata_y: bool,
+    initial_x: u64,
+    initial_y: u64,
+) -> Instruction {
+    initialize_ix(initializer, pool, fee, authority, emergency_delay, lp_decimals, metadata_x, metadata_y, initial_x, initial_y)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn initialize_ix(
+    initializer: &Pubkey,
+    pool: &PoolKeys,
+    fee: u16,
+    authority: Option<Pubkey>,
+    emergency_delay: i64,
+    lp_decimals: Option<u8>,
+    metadata_x: bool,
+    metadata_y: bool,
+    initial_x: u64,
+    initial_y: u64,
+) -> Instruction {
+    let seeded = initial_x > 0 || initial_y > 0;
+    let accounts = amm::accounts::Initialize {
+        initializer: *initializer,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp,
+        config: pool.config,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        lp_metadata: metadata_pda(&pool.mint_lp).0,
+        metadata_x: metadata_x.then(|| metadata_pda(&pool.mint_x).0),
+        metadata_y: metadata_y.then(|| metadata_pda(&pool.mint_y).0),
+        initializer_x: seeded.then(|| ata(initializer, &pool.mint_x)),
+        initializer_y: seeded.then(|| ata(initializer, &pool.mint_y)),
+        initializer_lp: seeded.then(|| ata(initializer, &pool.mint_lp)),
+        locked_lp: seeded.then(|| ata(&pool.config, &pool.mint_lp)),
+        position: seeded.then(|| position_pda(&pool.config, initializer).0),
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        token_metadata_program: mpl_token_metadata::ID,
+        rent: sysvar::rent::ID,
+    };
+
+    instruction(
+        accounts.to_account_metas(None),
+        amm::instruction::Initialize {
+            seed: pool.seed,
+            fee,
+            authority,
+            emergency_delay,
+            lp_decimals,
+            initial_x,
+            initial_y,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    max_x: u64,
+    max_y: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    deposit_ix(user, pool, amount, max_x, max_y, expiration, wrap_sol, (None, None), None, hook_accounts)
+}
+
+// deposit paid by user that mints the lp into lp_recipient, any token account of the lp mint
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_to(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    max_x: u64,
+    max_y: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    lp_recipient: &Pubkey,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    deposit_ix(user, pool, amount, max_x, max_y, expiration, wrap_sol, (None, None), Some(*lp_recipient), hook_accounts)
+}
+
+// deposit signed by a delegate: source_x/source_y pay instead of user's own atas, user has to be their approved
+// delegate, and the lp goes to lp_recipient (typically the owner) or else to user
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_from(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    max_x: u64,
+    max_y: u64,
+    expiration: Option<i64>,
+    source_x: Option<Pubkey>,
+    source_y: Option<Pubkey>,
+    lp_recipient: Option<Pubkey>,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    deposit_ix(user, pool, amount, max_x, max_y, expiration, false, (source_x, source_y), lp_recipient, hook_accounts)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn deposit_ix(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    max_x: u64,
+    max_y: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    (source_x, source_y): (Option<Pubkey>, Option<Pubkey>),
+    lp_recipient: Option<Pubkey>,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    let mut accounts = amm::accounts::Deposit {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp,
+        config: pool.config,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: user_ata(user, &pool.mint_x, source_x.is_some(), wrap_sol),
+        user_y: user_ata(user, &pool.mint_y, source_y.is_some(), wrap_sol),
+        user_lp: lp_recipient.is_none().then(|| ata(user, &pool.mint_lp)),
+        source_x,
+        source_y,
+        lp_recipient,
+        position: position_pda(&pool.config, user).0,
+        whitelist_entry: whitelist_entry(pool, user),
+        hook_program: pool.hook_program,
+        wsol: wsol(user, wrap_sol),
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend_from_slice(hook_accounts);
+
+    instruction(
+        accounts,
+        amm::instruction::Deposit {
+            amount,
+            max_x,
+            max_y,
+            expiration,
+            wrap_sol,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    is_x: bool,
+    min: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    swap_ix(user, pool, amount, is_x, min, expiration, wrap_sol, None, None, hook_accounts)
+}
+
+// swap paid by user that sends the output to recipient, any token account of the mint being bought
+#[allow(clippy::too_many_arguments)]
+pub fn swap_to(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    is_x: bool,
+    min: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    recipient: &Pubkey,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    swap_ix(user, pool, amount, is_x, min, expiration, wrap_sol, None, Some(*recipient), hook_accounts)
+}
+
+// swap signed by a delegate: sells out of source, which user has to be the approved delegate of,
+// and pays recipient (typically the owner) or else user
+#[allow(clippy::too_many_arguments)]
+pub fn swap_from(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    is_x: bool,
+    min: u64,
+    expiration: Option<i64>,
+    source: &Pubkey,
+    recipient: Option<Pubkey>,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    swap_ix(user, pool, amount, is_x, min, expiration, false, Some(*source), recipient, hook_accounts)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn swap_ix(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    is_x: bool,
+    min: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    source: Option<Pubkey>,
+    recipient: Option<Pubkey>,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    let (mint_in, mint_out) = order_mints(pool, is_x);
+    let user_in = user_ata(user, &mint_in, source.is_some(), wrap_sol);
+    let user_out = user_ata(user, &mint_out, recipient.is_some(), wrap_sol);
+    let (user_x, user_y) = match is_x {
+        true => (user_in, user_out),
+        false => (user_out, user_in),
+    };
+
+    let mut accounts = amm::accounts::Swap {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp,
+        config: pool.config,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x,
+        user_y,
+        source,
+        recipient,
+        whitelist_entry: whitelist_entry(pool, user),
+        hook_program: pool.hook_program,
+        wsol: wsol(user, wrap_sol),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend_from_slice(hook_accounts);
+
+    instruction(
+        accounts,
+        amm::instruction::Swap {
+            amount,
+            is_x,
+            min,
+            expiration,
+            wrap_sol,
+        },
+    )
+}
+
+fn withdraw_accounts(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    with_position: bool,
+    recipients: Option<(Pubkey, Pubkey)>,
+    wrap_sol: bool,
+) -> amm::accounts::Withdraw {
+    amm::accounts::Withdraw {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        mint_lp: pool.mint_lp,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: user_ata(user, &pool.mint_x, recipients.is_some(), wrap_sol),
+        user_y: user_ata(user, &pool.mint_y, recipients.is_some(), wrap_sol),
+        user_lp: ata(user, &pool.mint_lp),
+        position: with_position.then(|| position_pda(&pool.config, user).0),
+        recipient_x: recipients.map(|(x, _)| x),
+        recipient_y: recipients.map(|(_, y)| y),
+        whitelist_entry: whitelist_entry(pool, user),
+        hook_program: pool.hook_program,
+        wsol: wsol(user, wrap_sol),
+        token_program: token::ID,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+    }
+}
+
+// with_position: whether the user has an LpPosition (everyone who deposited does), so its fees get settled
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    with_position: bool,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    withdraw_ix(user, pool, amount, min_x, min_y, expiration, wrap_sol, with_position, None, hook_accounts)
+}
+
+// withdraw of user's lp that pays x to recipient_x and y to recipient_y, any token accounts of the two mints
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_to(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    with_position: bool,
+    recipient_x: &Pubkey,
+    recipient_y: &Pubkey,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    let recipients = Some((*recipient_x, *recipient_y));
+    withdraw_ix(user, pool, amount, min_x, min_y, expiration, wrap_sol, with_position, recipients, hook_accounts)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn withdraw_ix(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+    expiration: Option<i64>,
+    wrap_sol: bool,
+    with_position: bool,
+    recipients: Option<(Pubkey, Pubkey)>,
+    hook_accounts: &[AccountMeta],
+) -> Instruction {
+    let mut accounts = withdraw_accounts(user, pool, with_position, recipients, wrap_sol).to_account_metas(None);
+    accounts.extend_from_slice(hook_accounts);
+
+    instruction(
+        accounts,
+        amm::instruction::Withdraw {
+            amount,
+            min_x,
+            min_y,
+            expiration,
+            wrap_sol,
+        },
+    )
+}
+
+pub fn emergency_withdraw(
+    user: &Pubkey,
+    pool: &PoolKeys,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+    with_position: bool,
+) -> Instruction {
+    instruction(
+        withdraw_accounts(user, pool, with_position, None, false).to_account_metas(None),
+        amm::instruction::EmergencyWithdraw { amount, min_x, min_y },
+    )
+}
+
+pub fn claim_fees(user: &Pubkey, pool: &PoolKeys, hook_accounts: &[AccountMeta]) -> Instruction {
+    let mut accounts = amm::accounts::ClaimFees {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        mint_lp: pool.mint_lp,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: ata(user, &pool.mint_x),
+        user_y: ata(user, &pool.mint_y),
+        user_lp: ata(user, &pool.mint_lp),
+        position: position_pda(&pool.config, user).0,
+        whitelist_entry: whitelist_entry(pool, user),
+        hook_program: pool.hook_program,
+        token_program: token::ID,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend_from_slice(hook_accounts);
+
+    instruction(accounts, amm::instruction::ClaimFees {})
+}
+
+fn update_accounts(authority: &Pubkey, pool: &PoolKeys) -> Vec<AccountMeta> {
+    amm::accounts::Update {
+        user: *authority,
+        config: pool.config,
+    }
+    .to_account_metas(None)
+}
+
+pub fn lock(authority: &Pubkey, pool: &PoolKeys) -> Instruction {
+    instruction(update_accounts(authority, pool), amm::instruction::Lock {})
+}
+
+pub fn unlock(authority: &Pubkey, pool: &PoolKeys) -> Instruction {
+    instruction(update_accounts(authority, pool), amm::instruction::Unlock {})
+}
+
+pub fn set_permissioned(authority: &Pubkey, pool: &PoolKeys, permissioned: bool) -> Instruction {
+    instruction(
+        update_accounts(authority, pool),
+        amm::instruction::SetPermissioned { permissioned },
+    )
+}
+
+pub fn set_hook(authority: &Pubkey, pool: &PoolKeys, hook_program: Option<Pubkey>) -> Instruction {
+    instruction(
+        update_accounts(authority, pool),
+        amm::instruction::SetHook { hook_program },
+    )
+}
+
+pub fn set_caps(
+    authority: &Pubkey,
+    pool: &PoolKeys,
+    max_swap_in_bps: u16,
+    deposit_cap: u64,
+    user_deposit_cap: u64,
+) -> Instruction {
+    instruction(
+        update_accounts(authority, pool),
+        amm::instruction::SetCaps { max_swap_in_bps, deposit_cap, user_deposit_cap },
+    )
+}
+
+pub fn set_circuit_breaker(
+    authority: &Pubkey,
+    pool: &PoolKeys,
+    max_swap_price_move_bps: u16,
+    max_slot_price_move_bps: u16,
+    pause_on_price_move: bool,
+) -> Instruction {
+    instruction(
+        update_accounts(authority, pool),
+        amm::instruction::SetCircuitBreaker { max_swap_price_move_bps, max_slot_price_move_bps, pause_on_price_move },
+    )
+}
+
+// pays whatever the vaults hold above the pool's reserves to recipient_x and recipient_y
+pub fn skim(authority: &Pubkey, pool: &PoolKeys, recipient_x: &Pubkey, recipient_y: &Pubkey) -> Instruction {
+    let accounts = amm::accounts::Skim {
+        authority: *authority,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        recipient_x: *recipient_x,
+        recipient_y: *recipient_y,
+        token_program: token::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::Skim {})
+}
+
+// adds whatever the vaults hold above the pool's reserves to the reserves
+pub fn sync(authority: &Pubkey, pool: &PoolKeys) -> Instruction {
+    let accounts = amm::accounts::SyncReserves {
+        authority: *authority,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::Sync {})
+}
+
+// seeded: the pool was seeded at initialize, so the lp it locked with the config gets burned first
+pub fn close_pool(authority: &Pubkey, pool: &PoolKeys, seeded: bool) -> Instruction {
+    let accounts = amm::accounts::ClosePool {
+        authority: *authority,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp,
+        config: pool.config,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        authority_x: ata(authority, &pool.mint_x),
+        authority_y: ata(authority, &pool.mint_y),
+        locked_lp: seeded.then(|| ata(&pool.config, &pool.mint_lp)),
+        token_program: token::ID,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::ClosePool {})
+}
+
+pub fn add_to_whitelist(authority: &Pubkey, pool: &PoolKeys, user: &Pubkey) -> Instruction {
+    let accounts = amm::accounts::AddToWhitelist {
+        authority: *authority,
+        config: pool.config,
+        whitelist_entry: whitelist_pda(&pool.config, user).0,
+        system_program: system_program::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::AddToWhitelist { user: *user })
+}
+
+pub fn remove_from_whitelist(authority: &Pubkey, pool: &PoolKeys, user: &Pubkey) -> Instruction {
+    let accounts = amm::accounts::RemoveFromWhitelist {
+        authority: *authority,
+        config: pool.config,
+        whitelist_entry: whitelist_pda(&pool.config, user).0,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::RemoveFromWhitelist {})
+}
+
+pub fn initialize_farm(authority: &Pubkey, pool: &PoolKeys) -> Instruction {
+    let farm = pool.farm();
+    let accounts = amm::accounts::InitializeFarm {
+        authority: *authority,
+        config: pool.config,
+        mint_lp: pool.mint_lp,
+        farm,
+        farm_lp: ata(&farm, &pool.mint_lp),
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::InitializeFarm {})
+}
+
+// registers reward_mint on the farm, emissions are paid from the farm's ata of it, see reward_vault
+pub fn add_reward(authority: &Pubkey, pool: &PoolKeys, reward_mint: &Pubkey, rate: u64) -> Instruction {
+    let farm = pool.farm();
+    let accounts = amm::accounts::AddReward {
+        authority: *authority,
+        config: pool.config,
+        farm,
+        reward_mint: *reward_mint,
+        reward_vault: ata(&farm, reward_mint),
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::AddReward { rate })
+}
+
+pub fn set_reward_rate(authority: &Pubkey, pool: &PoolKeys, index: u8, rate: u64) -> Instruction {
+    let accounts = amm::accounts::SetRewardRate {
+        authority: *authority,
+        config: pool.config,
+        farm: pool.farm(),
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::SetRewardRate { index, rate })
+}
+
+// the farm's token account the authority tops up with reward_mint
+pub fn reward_vault(pool: &PoolKeys, reward_mint: &Pubkey) -> Pubkey {
+    ata(&pool.farm(), reward_mint)
+}
+
+fn stake_accounts(user: &Pubkey, pool: &PoolKeys) -> Vec<AccountMeta> {
+    let farm = pool.farm();
+    amm::accounts::Stake {
+        user: *user,
+        config: pool.config,
+        mint_lp: pool.mint_lp,
+        farm,
+        farm_lp: ata(&farm, &pool.mint_lp),
+        user_lp: ata(user, &pool.mint_lp),
+        position: stake_pda(&farm, user).0,
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    }
+    .to_account_metas(None)
+}
+
+pub fn stake(user: &Pubkey, pool: &PoolKeys, amount: u64) -> Instruction {
+    instruction(stake_accounts(user, pool), amm::instruction::Stake { amount })
+}
+
+pub fn unstake(user: &Pubkey, pool: &PoolKeys, amount: u64) -> Instruction {
+    instruction(stake_accounts(user, pool), amm::instruction::Unstake { amount })
+}
+
+// pays what user earned of the reward at index, which has to be reward_mint, into user's ata of it
+pub fn claim_rewards(user: &Pubkey, pool: &PoolKeys, index: u8, reward_mint: &Pubkey) -> Instruction {
+    let farm = pool.farm();
+    let accounts = amm::accounts::ClaimRewards {
+        user: *user,
+        config: pool.config,
+        farm,
+        position: stake_pda(&farm, user).0,
+        reward_mint: *reward_mint,
+        reward_vault: ata(&farm, reward_mint),
+        user_reward: ata(user, reward_mint),
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::ClaimRewards { index })
+}
+
+// the input and output mints of an order selling x (is_x) or y
+fn order_mints(pool: &PoolKeys, is_x: bool) -> (Pubkey, Pubkey) {
+    match is_x {
+        true => (pool.mint_x, pool.mint_y),
+        false => (pool.mint_y, pool.mint_x),
+    }
+}
+
+pub fn place_limit_order(
+    owner: &Pubkey,
+    pool: &PoolKeys,
+    id: u64,
+    is_x: bool,
+    amount: u64,
+    min_out: u64,
+    bounty: u64,
+) -> Instruction {
+    let (mint_in, mint_out) = order_mints(pool, is_x);
+    let limit_order = limit_order_pda(&pool.config, owner, id).0;
+    let accounts = amm::accounts::PlaceLimitOrder {
+        owner: *owner,
+        mint_in,
+        mint_out,
+        config: pool.config,
+        limit_order,
+        escrow: ata(&limit_order, &mint_in),
+        owner_in: ata(owner, &mint_in),
+        owner_out: ata(owner, &mint_out),
+        whitelist_entry: whitelist_entry(pool, owner),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    instruction(
+        accounts.to_account_metas(None),
+        amm::instruction::PlaceLimitOrder {
+            id,
+            is_x,
+            amount,
+            min_out,
+            bounty,
+        },
+    )
+}
+
+// keeper_in: any token account of the order's input mint, the bounty is paid there
+pub fn fill_limit_order(keeper: &Pubkey, pool: &PoolKeys, owner: &Pubkey, id: u64, is_x: bool, keeper_in: &Pubkey) -> Instruction {
+    let (mint_in, mint_out) = order_mints(pool, is_x);
+    let limit_order = limit_order_pda(&pool.config, owner, id).0;
+    let accounts = amm::accounts::FillLimitOrder {
+        keeper: *keeper,
+        owner: *owner,
+        mint_in,
+        mint_out,
+        mint_lp: pool.mint_lp,
+        config: pool.config,
+        limit_order,
+        escrow: ata(&limit_order, &mint_in),
+        vault_in: ata(&pool.config, &mint_in),
+        vault_out: ata(&pool.config, &mint_out),
+        owner_out: ata(owner, &mint_out),
+        keeper_in: *keeper_in,
+        whitelist_entry: whitelist_entry(pool, owner),
+        token_program: token::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::FillLimitOrder {})
+}
+
+pub fn cancel_limit_order(owner: &Pubkey, pool: &PoolKeys, id: u64, is_x: bool) -> Instruction {
+    let (mint_in, _) = order_mints(pool, is_x);
+    let limit_order = limit_order_pda(&pool.config, owner, id).0;
+    let accounts = amm::accounts::CancelLimitOrder {
+        owner: *owner,
+        mint_in,
+        config: pool.config,
+        limit_order,
+        escrow: ata(&limit_order, &mint_in),
+        owner_in: ata(owner, &mint_in),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::CancelLimitOrder {})
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_twap_order(
+    owner: &Pubkey,
+    pool: &PoolKeys,
+    id: u64,
+    is_x: bool,
+    amount: u64,
+    slice_amount: u64,
+    interval: i64,
+    max_slippage_bps: u16,
+) -> Instruction {
+    let (mint_in, mint_out) = order_mints(pool, is_x);
+    let twap_order = twap_order_pda(&pool.config, owner, id).0;
+    let accounts = amm::accounts::PlaceTwapOrder {
+        owner: *owner,
+        mint_in,
+        mint_out,
+        config: pool.config,
+        twap_order,
+        escrow: ata(&twap_order, &mint_in),
+        owner_in: ata(owner, &mint_in),
+        owner_out: ata(owner, &mint_out),
+        whitelist_entry: whitelist_entry(pool, owner),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    instruction(
+        accounts.to_account_metas(None),
+        amm::instruction::PlaceTwapOrder {
+            id,
+            is_x,
+            amount,
+            slice_amount,
+            interval,
+            max_slippage_bps,
+        },
+    )
+}
+
+pub fn execute_twap_slice(cranker: &Pubkey, pool: &PoolKeys, owner: &Pubkey, id: u64, is_x: bool) -> Instruction {
+    let (mint_in, mint_out) = order_mints(pool, is_x);
+    let twap_order = twap_order_pda(&pool.config, owner, id).0;
+    let accounts = amm::accounts::ExecuteTwapSlice {
+        cranker: *cranker,
+        owner: *owner,
+        mint_in,
+        mint_out,
+        mint_lp: pool.mint_lp,
+        config: pool.config,
+        twap_order,
+        escrow: ata(&twap_order, &mint_in),
+        vault_in: ata(&pool.config, &mint_in),
+        vault_out: ata(&pool.config, &mint_out),
+        owner_out: ata(owner, &mint_out),
+        whitelist_entry: whitelist_entry(pool, owner),
+        token_program: token::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::ExecuteTwapSlice {})
+}
+
+pub fn cancel_twap_order(owner: &Pubkey, pool: &PoolKeys, id: u64, is_x: bool) -> Instruction {
+    let (mint_in, _) = order_mints(pool, is_x);
+    let twap_order = twap_order_pda(&pool.config, owner, id).0;
+    let accounts = amm::accounts::CancelTwapOrder {
+        owner: *owner,
+        mint_in,
+        config: pool.config,
+        twap_order,
+        escrow: ata(&twap_order, &mint_in),
+        owner_in: ata(owner, &mint_in),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    };
+
+    instruction(accounts.to_account_metas(None), amm::instruction::CancelTwapOrder {})
+}
@@ -0,0 +1,13 @@
+// off-chain helpers for the amm program: everything a rust backend needs to talk to a pool
+// without hand assembling account lists
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+pub mod quote;
+
+pub use accounts::*;
+pub use instructions::*;
+pub use pda::*;
+pub use quote::*;
+
+pub use amm::ID as AMM_PROGRAM_ID;
@@ -0,0 +1,108 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::{associated_token::get_associated_token_address, metadata::mpl_token_metadata};
+
+use amm::{Config, ID};
+
+// every address below is derived the same way the program's seeds constraints derive it
+
+pub fn config_pda(seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config", seed.to_le_bytes().as_ref()], &ID)
+}
+
+pub fn lp_mint_pda(config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lp", config.as_ref()], &ID)
+}
+
+pub fn whitelist_pda(config: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"whitelist", config.as_ref(), user.as_ref()], &ID)
+}
+
+pub fn position_pda(config: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position", config.as_ref(), user.as_ref()], &ID)
+}
+
+// temporary wSOL account deposit, swap and withdraw open and close within one instruction with wrap_sol
+pub fn wsol_pda(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"wsol", user.as_ref()], &ID)
+}
+
+pub fn farm_pda(config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"farm", config.as_ref()], &ID)
+}
+
+pub fn stake_pda(farm: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stake", farm.as_ref(), user.as_ref()], &ID)
+}
+
+pub fn limit_order_pda(config: &Pubkey, owner: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"limit_order", config.as_ref(), owner.as_ref(), id.to_le_bytes().as_ref()],
+        &ID,
+    )
+}
+
+pub fn twap_order_pda(config: &Pubkey, owner: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"twap_order", config.as_ref(), owner.as_ref(), id.to_le_bytes().as_ref()],
+        &ID,
+    )
+}
+
+// metaplex metadata account of any mint, used for the lp mint and the pair
+pub fn metadata_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+}
+
+pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, mint)
+}
+
+// every address of one pool, plus the config flags that decide which optional accounts the instructions need
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub seed: u64,
+    pub config: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub mint_lp: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub permissioned: bool,
+    pub hook_program: Option<Pubkey>,
+}
+
+impl PoolKeys {
+    // keys of a pool that may not exist yet, e.g. before calling initialize
+    pub fn new(seed: u64, mint_x: Pubkey, mint_y: Pubkey) -> Self {
+        let (config, _) = config_pda(seed);
+        let (mint_lp, _) = lp_mint_pda(&config);
+
+        Self {
+            seed,
+            config,
+            mint_x,
+            mint_y,
+            mint_lp,
+            vault_x: ata(&config, &mint_x),
+            vault_y: ata(&config, &mint_y),
+            permissioned: false,
+            hook_program: None,
+        }
+    }
+
+    // keys of an existing pool, from its decoded config account
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            permissioned: config.permissioned,
+            hook_program: config.hook_program,
+            ..Self::new(config.seed, config.mint_x, config.mint_y)
+        }
+    }
+
+    pub fn farm(&self) -> Pubkey {
+        farm_pda(&self.config).0
+    }
+}
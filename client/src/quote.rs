@@ -0,0 +1,154 @@
+use amm::{
+    error::AmmError,
+    math::{self, Rounding},
+    Config,
+};
+
+// pure rust quotes that go through the same amm::math functions, with the same arguments and checks, as the program
+// so a quote that succeeds here is what the instruction will do against the same reserves. The one check a quote
+// can't see is the circuit breaker's per slot limit, which depends on the swaps before it in the same slot
+
+// the pool as an instruction sees it: the config's reserves and parameters, and the lp supply
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolState {
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+    pub fee: u16,
+    pub max_swap_in_bps: u16, // 0 is no limit, like the config's
+    pub max_swap_price_move_bps: u16,
+    pub deposit_cap: u64, // 0 is no limit, like the config's
+    pub user_deposit_cap: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    pub x: u64,
+    pub y: u64,
+}
+
+impl PoolState {
+    pub fn new(config: &Config, lp_supply: u64) -> Self {
+        Self {
+            reserve_x: config.reserve_x,
+            reserve_y: config.reserve_y,
+            lp_supply,
+            fee: config.fee,
+            max_swap_in_bps: config.max_swap_in_bps,
+            max_swap_price_move_bps: config.max_swap_price_move_bps,
+            deposit_cap: config.deposit_cap,
+            user_deposit_cap: config.user_deposit_cap,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lp_supply == 0 && self.reserve_x == 0 && self.reserve_y == 0
+    }
+
+    fn reserves(&self, is_x: bool) -> (u64, u64) {
+        match is_x {
+            true => (self.reserve_x, self.reserve_y),
+            false => (self.reserve_y, self.reserve_x),
+        }
+    }
+
+    // what Swap::swap does for amount of x (is_x) or y sold, with min as the slippage limit. A swap past the per swap
+    // price limit fails with PriceMoveExceeded, also on pools that lock instead since the swap doesn't trade there either
+    pub fn quote_swap(&self, amount: u64, is_x: bool, min: u64) -> Result<SwapQuote, AmmError> {
+        let (reserve_in, reserve_out) = self.reserves(is_x);
+        if self.max_swap_in_bps != 0 {
+            let max_in = math::mul_div(reserve_in, self.max_swap_in_bps as u64, math::FEE_DENOMINATOR, Rounding::Down)?;
+            if amount > max_in {
+                return Err(AmmError::SwapTooLarge);
+            }
+        }
+
+        let res = math::swap_out(reserve_in, reserve_out, amount, self.fee)?;
+        if res.amount_out == 0 {
+            return Err(AmmError::InvalidAmount);
+        }
+        if res.amount_out < min {
+            return Err(AmmError::SlippageExceeded);
+        }
+
+        if self.max_swap_price_move_bps != 0 {
+            let (new_x, new_y) = match is_x {
+                true => (self.reserve_x.checked_add(res.amount_in), self.reserve_y.checked_sub(res.amount_out)),
+                false => (self.reserve_x.checked_sub(res.amount_out), self.reserve_y.checked_add(res.amount_in)),
+            };
+            let (new_x, new_y) = (new_x.ok_or(AmmError::Overflow)?, new_y.ok_or(AmmError::Overflow)?);
+            let price_before = math::spot_price(self.reserve_x, self.reserve_y)?;
+            let price_after = math::spot_price(new_x, new_y)?;
+            if math::price_move_bps(price_before, price_after)? > self.max_swap_price_move_bps as u128 {
+                return Err(AmmError::PriceMoveExceeded);
+            }
+        }
+
+        Ok(SwapQuote {
+            amount_in: res.amount_in,
+            amount_out: res.amount_out,
+            fee: res.fee,
+        })
+    }
+
+    // how much x (is_x) or y has to be sold to receive exactly amount_out of the other side
+    pub fn quote_swap_exact_out(&self, amount_out: u64, is_x: bool) -> Result<SwapQuote, AmmError> {
+        let (reserve_in, reserve_out) = self.reserves(is_x);
+        let res = math::swap_in(reserve_in, reserve_out, amount_out, self.fee)?;
+
+        Ok(SwapQuote {
+            amount_in: res.amount_in,
+            amount_out: res.amount_out,
+            fee: res.fee,
+        })
+    }
+
+    // tokens Deposit::deposit takes to mint amount lp, for an empty pool that is max_x and max_y as given.
+    // deposited is the payer's LpPosition::deposited, 0 before their first deposit, checked against the user cap
+    pub fn quote_deposit(&self, amount: u64, max_x: u64, max_y: u64, deposited: u64) -> Result<LiquidityQuote, AmmError> {
+        if amount == 0 {
+            return Err(AmmError::InvalidAmount);
+        }
+
+        let (x, y) = match self.is_empty() {
+            true => (max_x, max_y),
+            false => {
+                let amounts = math::deposit_amounts(self.reserve_x, self.reserve_y, self.lp_supply, amount)?;
+                (amounts.x, amounts.y)
+            }
+        };
+        if x == 0 || y == 0 {
+            return Err(AmmError::InvalidAmount);
+        }
+
+        let supply = self.lp_supply.checked_add(amount).ok_or(AmmError::Overflow)?;
+        if self.deposit_cap != 0 && supply > self.deposit_cap {
+            return Err(AmmError::DepositCapExceeded);
+        }
+        let deposited = deposited.checked_add(amount).ok_or(AmmError::Overflow)?;
+        if self.user_deposit_cap != 0 && deposited > self.user_deposit_cap {
+            return Err(AmmError::UserDepositCapExceeded);
+        }
+
+        Ok(LiquidityQuote { x, y })
+    }
+
+    // tokens Withdraw::withdraw pays out for burning amount lp
+    pub fn quote_withdraw(&self, amount: u64) -> Result<LiquidityQuote, AmmError> {
+        let amounts = math::withdraw_amounts(self.reserve_x, self.reserve_y, self.lp_supply, amount)?;
+
+        Ok(LiquidityQuote { x: amounts.x, y: amounts.y })
+    }
+
+    // tokens Withdraw::emergency_withdraw pays out for burning amount lp, the same proportional split as quote_withdraw
+    pub fn quote_emergency_withdraw(&self, amount: u64) -> Result<LiquidityQuote, AmmError> {
+        self.quote_withdraw(amount)
+    }
+}
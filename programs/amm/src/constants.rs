@@ -2,3 +2,29 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+// first 8 bytes of sha256("global:amm_hook"), prefixed to the data of every hook cpi
+pub const HOOK_DISCRIMINATOR: [u8; 8] = [91, 92, 18, 130, 72, 237, 26, 103];
+
+// how many reward mints a single farm can emit
+pub const MAX_REWARDS: usize = 3;
+
+// scale of Farm reward accumulators, keeps precision when few tokens are staked
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+// lp mints can have at most 9 decimals, same as SOL
+pub const MAX_LP_DECIMALS: u8 = 9;
+
+// scale of Config fee growth accumulators
+pub const FEE_GROWTH_PRECISION: u128 = 1_000_000_000_000;
+
+// scale of spot prices and Config price accumulators
+pub const PRICE_PRECISION: u128 = 1_000_000_000_000;
+
+// lp locked away when initialize seeds a pool, so the supply never drops back to 0 while the pool is open
+// and the first lp can't inflate the value of a single lp token to round later depositors down. close_pool burns it
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+// symbol of every lp mint, and the longest name token metadata accepts
+pub const LP_SYMBOL: &str = "AMM-LP";
+pub const MAX_NAME_LENGTH: usize = 32;
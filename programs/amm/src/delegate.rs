@@ -0,0 +1,20 @@
+use anchor_lang::{prelude::*, solana_program::program_option::COption};
+use anchor_spl::token::TokenAccount;
+
+use crate::error::AmmError;
+
+// swap and deposit can spend from a source account the signer doesn't own, as long as the owner
+// approved the signer as its spl delegate for at least the amount being spent
+// the token program enforces the same thing on transfer, checking it here gives a clear error before any work is done
+
+pub fn check_spender(source: &TokenAccount, spender: &Pubkey, amount: u64) -> Result<()> {
+    if source.owner == *spender {
+        return Ok(());
+    }
+
+    require!(
+        source.delegate == COption::Some(*spender) && source.delegated_amount >= amount,
+        AmmError::NotDelegated
+    );
+    Ok(())
+}
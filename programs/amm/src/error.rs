@@ -1,5 +1,4 @@
 use anchor_lang::error_code;
-use constant_product_curve::CurveError;
 
 
 #[error_code]
@@ -26,6 +25,8 @@ pub enum AmmError {
     NoLiquidityInPool,
     #[msg("Bump error.")]
     BumpError,
+    // no longer raised since the curve crate was replaced by math.rs, kept so the codes after it don't shift.
+    // variants are only ever appended, DefaultError, BumpError and SwapError stay unused for the same reason
     #[msg("Curve error.")]
     CurveError,
     #[msg("Fee is greater than 100%. This is not a very good deal.")]
@@ -43,19 +44,45 @@ pub enum AmmError {
     #[msg("Zero balance.")]
     ZeroBalance,
     #[msg("Swap error.")]
-    SwapError
+    SwapError,
+    #[msg("Pool still has outstanding LP tokens.")]
+    PoolNotEmpty,
+    #[msg("Emergency withdraw is not available yet.")]
+    EmergencyWithdrawUnavailable,
+    #[msg("User is not whitelisted for this pool.")]
+    NotWhitelisted,
+    #[msg("Missing or invalid hook program.")]
+    InvalidHookProgram,
+    #[msg("Farm already has the maximum number of rewards.")]
+    TooManyRewards,
+    #[msg("Invalid reward index.")]
+    InvalidRewardIndex,
+    #[msg("Neither side of this pool is the native mint.")]
+    NoNativeMint,
+    #[msg("Signer is not the owner or an approved delegate of the source account.")]
+    NotDelegated,
+    #[msg("Orders are not available on pools with a hook program.")]
+    HookedPool,
+    #[msg("Pool price has not reached the order's limit.")]
+    LimitNotReached,
+    #[msg("The TWAP order's interval has not elapsed since its last slice.")]
+    TwapIntervalNotElapsed,
+    #[msg("Swap moves the price further than the pool allows.")]
+    PriceMoveExceeded,
+    #[msg("Swap input is larger than the pool allows per swap.")]
+    SwapTooLarge,
+    #[msg("Deposit would take the pool over its deposit cap.")]
+    DepositCapExceeded,
+    #[msg("Deposit would take this user over the per user deposit cap.")]
+    UserDepositCapExceeded,
+    #[msg("Initial liquidity needs the initializer's token accounts, the lp accounts and the position.")]
+    MissingLiquidityAccounts,
+    #[msg("wrap_sol needs the temporary wSOL account.")]
+    MissingWsolAccount,
+    #[msg("The user's token account for this side is missing.")]
+    MissingTokenAccount,
+    #[msg("Initial liquidity accounts were passed without any initial liquidity.")]
+    UnusedLiquidityAccounts,
+    #[msg("Deposit needs exactly one of user_lp and lp_recipient.")]
+    InvalidLpDestination
 }
-
-impl From<CurveError> for AmmError {
-    fn from(error: CurveError) -> AmmError {
-        match error {
-            CurveError::InvalidPrecision => AmmError::InvalidPrecision,
-            CurveError::Overflow => AmmError::Overflow,
-            CurveError::Underflow => AmmError::Underflow,
-            CurveError::InvalidFeeAmount => AmmError::InvalidFee,
-            CurveError::InsufficientBalance => AmmError::InsufficientBalance,
-            CurveError::ZeroBalance => AmmError::ZeroBalance,
-            CurveError::SlippageLimitExceeded => AmmError::SlippageExceeded,
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,12 @@
+use anchor_lang::prelude::*;
+
+// a swap moved the price past one of the config's limits and the pool locked itself instead of reverting
+// the swap that tripped it does not go through, the authority has to unlock the pool again
+#[event]
+pub struct CircuitBreakerTripped {
+    pub config: Pubkey,
+    pub slot: u64,
+    pub slot_start_price: u128, // y per x before the first swap of the slot, scaled by PRICE_PRECISION
+    pub price_before: u128, // y per x before the swap that tripped it
+    pub price_after: u128, // y per x the swap would have left behind
+}
@@ -0,0 +1,90 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke,
+    },
+};
+
+use crate::{error::AmmError, Config, HOOK_DISCRIMINATOR};
+
+// optional hook program registered on the config, cpi'd before and after swap, deposit, withdraw and claim_fees
+/*
+    instruction the hook program receives:
+    - data: HOOK_DISCRIMINATOR followed by the borsh encoded HookArgs
+    - accounts:
+        0. config (read only)
+        1. user (read only, signer)
+        2.. whatever was passed as remaining accounts to the amm instruction, in the same order
+    if the hook returns an error the whole amm instruction fails
+*/
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookStage {
+    Before,
+    After,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookAction {
+    Swap,
+    Deposit,
+    Withdraw,
+    ClaimFees,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HookArgs {
+    pub stage: HookStage,
+    pub action: HookAction,
+    pub amount: u64, // lp amount for deposit/withdraw, amount sold for swap, lp burned for claim_fees
+    pub is_x: bool, // swap direction, always true for deposit/withdraw/claim_fees
+    // Before: the user's limits (max_x/max_y, min_x/min_y, or amount/min for a swap)
+    // After: the token amounts that actually moved
+    // claim_fees has no limits, both stages get the fees paid out
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+pub fn invoke_hook<'info>(
+    config: &Account<'info, Config>,
+    hook_program: &Option<UncheckedAccount<'info>>,
+    user: &Signer<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    args: HookArgs,
+) -> Result<()> {
+    // pools without a hook skip this entirely
+    let Some(expected) = config.hook_program else {
+        return Ok(());
+    };
+
+    let hook_program = hook_program.as_ref().ok_or(AmmError::InvalidHookProgram)?;
+    require_keys_eq!(hook_program.key(), expected, AmmError::InvalidHookProgram);
+    require!(hook_program.executable, AmmError::InvalidHookProgram);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config.key(), false),
+        AccountMeta::new_readonly(user.key(), true),
+    ];
+    let mut account_infos = vec![config.to_account_info(), user.to_account_info()];
+
+    for account in remaining_accounts {
+        accounts.push(match account.is_writable {
+            true => AccountMeta::new(account.key(), account.is_signer),
+            false => AccountMeta::new_readonly(account.key(), account.is_signer),
+        });
+        account_infos.push(account.clone());
+    }
+    account_infos.push(hook_program.to_account_info());
+
+    let mut data = HOOK_DISCRIMINATOR.to_vec();
+    args.serialize(&mut data).map_err(ProgramError::from)?;
+
+    let ix = Instruction {
+        program_id: expected,
+        accounts,
+        data,
+    };
+
+    invoke(&ix, &account_infos).map_err(Into::into)
+}
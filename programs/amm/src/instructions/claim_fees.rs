@@ -0,0 +1,190 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    error::AmmError,
+    hook::{invoke_hook, HookAction, HookArgs, HookStage},
+    math, Config, LpPosition, WhitelistEntry,
+};
+
+// lets an lp take the fees they earned out of the pool separately from their principal
+
+/*
+    accounts in the context struct:
+    - user
+    - mint_x, mint_y, mint_lp
+    - config
+    - vault_x, vault_y
+    - user_x, user_y, user_lp
+    - position
+    - whitelist_entry, hook_program (optional)
+    - the three accounts
+*/
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_x: Account<'info, Mint>,
+    pub mint_y: Account<'info, Mint>,
+
+    #[account(
+        mut, // fees are paid out of the reserves
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = config.lp_decimals
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = user
+    )]
+    pub user_x: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = user
+    )]
+    pub user_y: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user
+    )]
+    pub user_lp: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = config,
+        has_one = user,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, LpPosition>,
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), user.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // only needed on permissioned pools
+
+    /// CHECK: only used when config.hook_program is set, the key is checked against it in invoke_hook
+    pub hook_program: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/*
+    - fees were compounded into the reserves, so every lp token is backed by part of them
+    - paying the fees out without burning anything would dilute every other lp, so we burn the lp tokens that back them
+    - the burn is rounded up and sized by whichever side needs more lp, and the claimer is paid the burned lp's full
+      share of both reserves like a withdraw, which covers the fees on each side, so nothing of the other side is left behind
+    - the burn follows the reserves at execution, a swap in front of the claim can only grow it. That turns more of the
+      position into tokens at the pool's price rather than losing value, so there is no min-out, callers wanting a fixed
+      burn use withdraw instead
+*/
+impl<'info> ClaimFees<'info> {
+    pub fn claim_fees(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.mint_lp.supply > 0, AmmError::NoLiquidityInPool);
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+
+        let (fees_x, fees_y) = self.position.earned(&self.config, self.user_lp.amount)?;
+        require!(fees_x > 0 || fees_y > 0, AmmError::ZeroBalance);
+
+        let burn_amount = math::lp_for_amount(fees_x, self.config.reserve_x, self.mint_lp.supply)?
+            .max(math::lp_for_amount(fees_y, self.config.reserve_y, self.mint_lp.supply)?);
+        require!(self.user_lp.amount >= burn_amount, AmmError::InsufficientBalance);
+        let amounts = math::withdraw_amounts(self.config.reserve_x, self.config.reserve_y, self.mint_lp.supply, burn_amount)?;
+
+        // the hook sees the tokens about to be paid out and the lp burned for them, at both stages
+        let args = |stage| HookArgs {
+            stage,
+            action: HookAction::ClaimFees,
+            amount: burn_amount,
+            is_x: true,
+            amount_x: amounts.x,
+            amount_y: amounts.y,
+        };
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, args(HookStage::Before))?;
+
+        self.position.settle(&self.config, self.user_lp.amount, self.user_lp.amount - burn_amount)?;
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+        self.position.deposited = self.position.deposited.saturating_sub(burn_amount);
+
+        self.withdraw_token(true, amounts.x)?;
+        self.withdraw_token(false, amounts.y)?;
+        self.config.sub_reserves(amounts.x, amounts.y)?;
+        self.burn(burn_amount)?;
+
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, args(HookStage::After))
+    }
+
+    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to) = match is_x {
+            true => (self.vault_x.to_account_info(), self.user_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.user_y.to_account_info()),
+        };
+
+        let cpi_accounts = Transfer {
+            from,
+            to,
+            authority: self.config.to_account_info(),
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+
+        transfer(ctx, amount)
+    }
+
+    pub fn burn(&mut self, amount: u64) -> Result<()> {
+        let cpi_accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.user_lp.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        let ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        burn(ctx, amount)
+    }
+}
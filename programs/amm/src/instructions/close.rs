@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{burn, close_account, transfer, Burn, CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{error::AmmError, Config};
+
+// this instruction lets the authority close a drained pool and get the rent back
+/*
+    accounts required:
+    - authority
+    - mint_x, mint_y, mint_lp
+    - config
+    - vault_x, vault_y
+    - authority_x, authority_y (receive any dust left in the vaults)
+    - locked_lp (optional, only on pools seeded at initialize)
+    - the three accounts
+*/
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint_x: Account<'info, Mint>,
+    pub mint_y: Account<'info, Mint>,
+
+    #[account(
+        mut, // the locked lp gets burned
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = config.lp_decimals
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        close = authority, // config rent goes back to the authority
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_x,
+        associated_token::authority = authority
+    )]
+    pub authority_x: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_y,
+        associated_token::authority = authority
+    )]
+    pub authority_y: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = config
+    )]
+    pub locked_lp: Option<Account<'info, TokenAccount>>, // MINIMUM_LIQUIDITY locked by initialize, burned and closed here
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/*
+    - the pool can only be closed once every lp token has been burned, so nobody has a claim on the vaults anymore.
+      The lp a seeded pool locked with the config at initialize is the exception, it belongs to nobody and gets burned here
+    - whatever dust is still sitting in the vaults is swept to the authority, then both vaults are closed
+    - the lp mint and its metadata stay, the classic token program cannot close mints. The mint sits at the
+      [b"lp", config] address, so initialize can never run again for the same seed: seeds are single use,
+      a new pool for the pair needs a new seed
+    - orders still resting on the pool don't hold it open, cancelling them only needs the config's key
+*/
+impl<'info> ClosePool<'info> {
+    pub fn close_pool(&mut self) -> Result<()> {
+        require!(self.config.authority.is_some(), AmmError::NoAuthoritySet);
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+        let locked = self.locked_lp.as_ref().map_or(0, |locked_lp| locked_lp.amount);
+        require!(self.mint_lp.supply == locked, AmmError::PoolNotEmpty);
+
+        self.burn_locked_lp()?;
+        self.sweep_and_close(true)?;
+        self.sweep_and_close(false)?;
+        Ok(())
+    }
+
+    // burns whatever lp the config holds and closes its account, the rent goes to the authority
+    pub fn burn_locked_lp(&mut self) -> Result<()> {
+        let Some(locked_lp) = &self.locked_lp else {
+            return Ok(());
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        if locked_lp.amount > 0 {
+            let cpi_accounts = Burn {
+                mint: self.mint_lp.to_account_info(),
+                from: locked_lp.to_account_info(),
+                authority: self.config.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+            burn(ctx, locked_lp.amount)?;
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: locked_lp.to_account_info(),
+            destination: self.authority.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+
+        close_account(ctx)
+    }
+
+    // moves any leftover tokens to the authority and closes the vault
+    pub fn sweep_and_close(&mut self, is_x: bool) -> Result<()> {
+        let (vault, to, dust) = match is_x {
+            true => (self.vault_x.to_account_info(), self.authority_x.to_account_info(), self.vault_x.amount),
+            false => (self.vault_y.to_account_info(), self.authority_y.to_account_info(), self.vault_y.amount),
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        if dust > 0 {
+            let cpi_accounts = Transfer {
+                from: vault.clone(),
+                to,
+                authority: self.config.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+            transfer(ctx, dust)?;
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: vault,
+            destination: self.authority.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+
+        close_account(ctx)
+    }
+}
@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token::{accessor::mint, mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer}};
-use constant_product_curve::ConstantProduct;
+use anchor_spl::{associated_token::AssociatedToken, token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer}};
 
-use crate::{state::Config};
+use crate::{state::{Config, LpPosition, WhitelistEntry}};
+use crate::delegate::check_spender;
+use crate::hook::{invoke_hook, HookAction, HookArgs, HookStage};
+use crate::math;
+use crate::native::{close_wsol, native_side, open_wsol};
 use crate::{error::AmmError};
 
 // this is helpful for liquidity providers in order to deposit their tokens
@@ -13,11 +16,13 @@ use crate::{error::AmmError};
     - mint_x, mint_y, mint_lp
     - config
     - vault_x, vault_y
-    - user_x, user_y, user_lp
+    - user_x, user_y (optional, only the ones the deposit pays from)
+    - user_lp, lp_recipient (exactly one of them, whichever receives the lp)
+    - source_x, source_y (optional)
+    - wsol (optional, with wrap_sol)
     - the three accounts
 */
 #[derive(Accounts)]
-#[instruction(seed:u64)]
 pub struct Deposit<'info>{
 
     #[account(mut)]
@@ -27,15 +32,16 @@ pub struct Deposit<'info>{
 
     #[account(
         mut, // mutable because we will mint and change it's state
-        mint::decimals = 6,
+        mint::decimals = config.lp_decimals,
         mint::authority = config,
     )]
     pub mint_lp: Account<'info,Mint>, // lp tokens to be given to the users
 
     #[account(
+        mut, // reserves grow with the deposit
         has_one = mint_x,
         has_one = mint_y,
-        seeds = [b"config", seed.to_le_bytes().as_ref()],
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump
     )]
     pub config: Account<'info,Config>,
@@ -55,18 +61,20 @@ pub struct Deposit<'info>{
     pub vault_y: Account<'info,TokenAccount>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
     )]
-    pub user_x: Account<'info,TokenAccount>,
+    pub user_x: Option<Account<'info,TokenAccount>>, // left out when source_x or wrap_sol pays the x side
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
     )]
-    pub user_y: Account<'info,TokenAccount>,
+    pub user_y: Option<Account<'info,TokenAccount>>, // left out when source_y or wrap_sol pays the y side
 
     #[account(
         init_if_needed,
@@ -74,7 +82,51 @@ pub struct Deposit<'info>{
         associated_token::mint = mint_lp,
         associated_token::authority = user,
     )]
-    pub user_lp: Account<'info,TokenAccount>,
+    pub user_lp: Option<Account<'info,TokenAccount>>, // left out when lp_recipient receives the lp
+
+    #[account(
+        mut,
+        token::mint = mint_x,
+    )]
+    pub source_x: Option<Account<'info,TokenAccount>>, // pays x instead of user_x, user has to own it or be its delegate
+
+    #[account(
+        mut,
+        token::mint = mint_y,
+    )]
+    pub source_y: Option<Account<'info,TokenAccount>>, // pays y instead of user_y, user has to own it or be its delegate
+
+    #[account(
+        mut,
+        token::mint = mint_lp,
+    )]
+    pub lp_recipient: Option<Account<'info,TokenAccount>>, // receives the lp instead of user_lp, only its mint is checked
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + LpPosition::INIT_SPACE,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, LpPosition>, // tracks the fees this lp has earned
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), user.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // only needed on permissioned pools
+
+    /// CHECK: only used when config.hook_program is set, the key is checked against it in invoke_hook
+    pub hook_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: created and closed by this instruction with wrap_sol, see native.rs
+    #[account(
+        mut,
+        seeds = [b"wsol", user.key().as_ref()],
+        bump
+    )]
+    pub wsol: Option<UncheckedAccount<'info>>,
 
     pub system_program: Program<'info,System>,
     pub token_program: Program<'info,Token>,
@@ -86,46 +138,124 @@ impl <'info> Deposit<'info> {
 
     // here amount is the user desired lp token amount
     // here users are basically DEPOSITING X AND Y TOKENS TO PROVIDE LIQUIDITY and quote their amount of lp tokens
-    pub fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
+    // expiration is an optional unix timestamp after which the deposit should no longer land
+    // wrap_sol pays the wSOL side straight from the user's lamports, see native.rs
+    #[allow(clippy::too_many_arguments)]
+    pub fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64, expiration: Option<i64>, wrap_sol: bool, remaining_accounts: &[AccountInfo<'info>], bumps: &DepositBumps) -> Result<()> {
 
         // if required condition is not true, then returns the mentioned error
-        require!(self.config.locked == false, AmmError::PoolLocked);
+        require!(!self.config.locked, AmmError::PoolLocked);
         require!(amount > 0, AmmError::InvalidAmount);
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+        require!(self.user_lp.is_some() != self.lp_recipient.is_some(), AmmError::InvalidLpDestination);
+        if let Some(expiration) = expiration {
+            require!(Clock::get()?.unix_timestamp <= expiration, AmmError::OfferExpired);
+        }
+
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, HookArgs {
+            stage: HookStage::Before,
+            action: HookAction::Deposit,
+            amount,
+            is_x: true,
+            amount_x: max_x,
+            amount_y: max_y,
+        })?;
 
         let (x, y) = match self.mint_lp.supply == 0
-            && self.vault_x.amount == 0
-            && self.vault_y.amount == 0
+            && self.config.reserve_x == 0
+            && self.config.reserve_y == 0
         { // if we in the initial stage, then we can set max_x and max_y as x and y
             true => (max_x, max_y),
             false => { // we will fetch the x, y deposit amounts
-                let amount = ConstantProduct::xy_deposit_amounts_from_l(
-                    self.vault_x.amount,
-                    self.vault_y.amount,
+                let amounts = math::deposit_amounts(
+                    self.config.reserve_x,
+                    self.config.reserve_y,
                     self.mint_lp.supply,
                     amount,
-                    6,
-                )
-                .unwrap();
-                (amount.x, amount.y)
+                )?;
+                (amounts.x, amounts.y)
             }
         };
+        // amounts round up, so this only trips when a vault is empty while lp is outstanding
+        require!(x > 0 && y > 0, AmmError::InvalidAmount);
         require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
-        self.deposit_tokens(true, x)?;
-        self.deposit_tokens(false, y)?;
-        self.mint_lp_token(amount)
+
+        // guarded pools cap the lp supply and what each user deposits, see Config
+        self.config.check_deposit_caps(amount, self.mint_lp.supply, self.position.deposited)?;
+        self.position.deposited = self.position.deposited.checked_add(amount).ok_or(AmmError::Overflow)?;
+
+        // sources owned by someone else work when they approved user as delegate for the amounts
+        if let Some(source_x) = &self.source_x {
+            check_spender(source_x, &self.user.key(), x)?;
+        }
+        if let Some(source_y) = &self.source_y {
+            check_spender(source_y, &self.user.key(), y)?;
+        }
+
+        // the native side paid in SOL, a side paid from a source needs no wrapping
+        let wrapped = match wrap_sol {
+            true => Some(native_side(&self.mint_x.key(), &self.mint_y.key())?),
+            false => None,
+        };
+        let wrapped = wrapped.filter(|native_x| match native_x {
+            true => self.source_x.is_none(),
+            false => self.source_y.is_none(),
+        });
+        if let Some(native_x) = wrapped {
+            let (amount, native_mint) = match native_x {
+                true => (x, &self.mint_x),
+                false => (y, &self.mint_y),
+            };
+            open_wsol(&self.user, &self.wsol, bumps.wsol, native_mint, &self.system_program, &self.token_program, amount)?;
+        }
+
+        self.deposit_tokens(true, x, wrapped == Some(true))?;
+        self.deposit_tokens(false, y, wrapped == Some(false))?;
+        self.config.add_reserves(x, y)?;
+        // freshly created positions come in zeroed, they belong to the payer even when the lp goes elsewhere
+        if self.position.config == Pubkey::default() {
+            self.position.config = self.config.key();
+            self.position.user = self.user.key();
+            self.position.bump = bumps.position;
+        }
+        // the payer's position only grows when the lp stays with them
+        if let Some(lp_balance) = self.user_lp.as_ref().map(|user_lp| user_lp.amount) {
+            self.settle_position(lp_balance, amount)?;
+        }
+        self.mint_lp_token(amount)?;
+
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, HookArgs {
+            stage: HookStage::After,
+            action: HookAction::Deposit,
+            amount,
+            is_x: true,
+            amount_x: x,
+            amount_y: y,
+        })?;
+
+        // the temporary account is empty by now, closing it refunds its rent
+        if wrapped.is_some() {
+            close_wsol(&self.user, &self.wsol, &self.token_program)?;
+        }
+        Ok(())
     }
-    
-    pub fn deposit_tokens(&mut self, is_x:bool, amount:u64) -> Result<()>{
+
+    // from_wsol: the side is paid out of the temporary wSOL account
+    pub fn deposit_tokens(&mut self, is_x:bool, amount:u64, from_wsol: bool) -> Result<()>{
 
         let (from,to) = match is_x {
-            true => (
-                self.user_x.to_account_info(),
-                self.vault_x.to_account_info()
-            ),
-            false => (
-                self.user_y.to_account_info(),
-                self.vault_y.to_account_info()
-            )
+            true => (&self.user_x, self.vault_x.to_account_info()),
+            false => (&self.user_y, self.vault_y.to_account_info()),
+        };
+        let source = match is_x {
+            true => &self.source_x,
+            false => &self.source_y,
+        };
+        let from = match (source, &self.wsol, from) {
+            (Some(source), _, _) => source.to_account_info(),
+            (None, Some(wsol), _) if from_wsol => wsol.to_account_info(),
+            (None, _, Some(from)) => from.to_account_info(),
+            _ => return err!(AmmError::MissingTokenAccount),
         };
 
         let cpi_program = self.token_program.to_account_info();
@@ -143,13 +273,23 @@ impl <'info> Deposit<'info> {
         Ok(())
     }
 
+    // settles fees earned so far before the lp balance grows by amount
+    pub fn settle_position(&mut self, lp_balance: u64, amount: u64) -> Result<()> {
+        let new_lp_amount = lp_balance.checked_add(amount).ok_or(AmmError::Overflow)?;
+        self.position.settle(&self.config, lp_balance, new_lp_amount)
+    }
+
     pub fn mint_lp_token(&mut self, amount: u64)->Result<()>{
 
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = MintTo {
     mint: self.mint_lp.to_account_info(),
-    to: self.user_lp.to_account_info(),
+    to: match (&self.lp_recipient, &self.user_lp) {
+        (Some(lp_recipient), _) => lp_recipient.to_account_info(),
+        (None, Some(user_lp)) => user_lp.to_account_info(),
+        (None, None) => return err!(AmmError::InvalidLpDestination),
+    },
     authority: self.config.to_account_info(), // Config is the mint authority
 };
 
@@ -0,0 +1,344 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{transfer, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{error::AmmError, Config, Farm, RewardInfo, StakePosition, MAX_REWARDS};
+
+// liquidity mining: lps stake their lp tokens in the pool's farm and earn the reward mints the authority configured
+/*
+    instructions:
+    - initialize_farm: authority creates the farm and its lp vault
+    - add_reward / set_reward_rate: authority registers a reward mint and controls its emission rate
+    - stake / unstake: users move lp tokens in and out of the farm
+    - claim_rewards: users collect what they earned for one reward mint
+*/
+
+#[derive(Accounts)]
+pub struct InitializeFarm<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = config.lp_decimals
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Farm::INIT_SPACE,
+        seeds = [b"farm", config.key().as_ref()],
+        bump
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm
+    )]
+    pub farm_lp: Account<'info, TokenAccount>, // staked lp tokens live here
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> InitializeFarm<'info> {
+    pub fn init_farm(&mut self, bumps: &InitializeFarmBumps) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+
+        self.farm.set_inner(Farm {
+            config: self.config.key(),
+            total_staked: 0,
+            last_update: Clock::get()?.unix_timestamp,
+            rewards: Vec::new(),
+            bump: bumps.farm,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AddReward<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        has_one = config,
+        seeds = [b"farm", config.key().as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Account<'info, Farm>,
+
+    pub reward_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm
+    )]
+    pub reward_vault: Account<'info, TokenAccount>, // the authority funds emissions by transferring into this ata
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> AddReward<'info> {
+    pub fn add_reward(&mut self, rate: u64) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+        require!(self.farm.rewards.len() < MAX_REWARDS, AmmError::TooManyRewards);
+        require!(
+            self.farm.rewards.iter().all(|r| r.mint != self.reward_mint.key()),
+            AmmError::InvalidToken
+        );
+
+        // settle the existing rewards first, the new one starts accruing from now
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.farm.rewards.push(RewardInfo {
+            mint: self.reward_mint.key(),
+            rate,
+            acc_per_share: 0,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetRewardRate<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        has_one = config,
+        seeds = [b"farm", config.key().as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Account<'info, Farm>,
+}
+
+impl<'info> SetRewardRate<'info> {
+    pub fn set_reward_rate(&mut self, index: u8, rate: u64) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+        require!((index as usize) < self.farm.rewards.len(), AmmError::InvalidRewardIndex);
+
+        // everything up to now is emitted at the old rate
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.farm.rewards[index as usize].rate = rate;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = config.lp_decimals
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        has_one = config,
+        seeds = [b"farm", config.key().as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm
+    )]
+    pub farm_lp: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user
+    )]
+    pub user_lp: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakePosition::INIT_SPACE,
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> Stake<'info> {
+    pub fn stake(&mut self, amount: u64, bumps: &StakeBumps) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        // freshly created positions come in zeroed
+        if self.position.farm == Pubkey::default() {
+            self.position.farm = self.farm.key();
+            self.position.user = self.user.key();
+            self.position.bump = bumps.position;
+        }
+
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.position.settle(&self.farm)?;
+
+        let cpi_accounts = Transfer {
+            from: self.user_lp.to_account_info(),
+            to: self.farm_lp.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        let ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer(ctx, amount)?;
+
+        self.position.amount = self.position.amount.checked_add(amount).ok_or(AmmError::Overflow)?;
+        self.farm.total_staked = self.farm.total_staked.checked_add(amount).ok_or(AmmError::Overflow)?;
+        self.position.reset_debt(&self.farm)
+    }
+
+    pub fn unstake(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+        require!(self.position.amount >= amount, AmmError::InsufficientBalance);
+
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.position.settle(&self.farm)?;
+
+        let config_key = self.config.key();
+        let seeds = &[&b"farm"[..], config_key.as_ref(), &[self.farm.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: self.farm_lp.to_account_info(),
+            to: self.user_lp.to_account_info(),
+            authority: self.farm.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+        transfer(ctx, amount)?;
+
+        self.position.amount -= amount;
+        // can't trip, total_staked is the sum of every position's amount and this one holds at least amount
+        self.farm.total_staked = self.farm.total_staked.checked_sub(amount).ok_or(AmmError::Underflow)?;
+        self.position.reset_debt(&self.farm)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        has_one = config,
+        seeds = [b"farm", config.key().as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        has_one = farm,
+        has_one = user,
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, StakePosition>,
+
+    pub reward_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = reward_mint,
+        associated_token::authority = user
+    )]
+    pub user_reward: Account<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> ClaimRewards<'info> {
+    pub fn claim_rewards(&mut self, index: u8) -> Result<()> {
+        let index = index as usize;
+        require!(index < self.farm.rewards.len(), AmmError::InvalidRewardIndex);
+        require!(self.farm.rewards[index].mint == self.reward_mint.key(), AmmError::InvalidToken);
+
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.position.settle(&self.farm)?;
+
+        let amount = self.position.pending[index];
+        require!(amount > 0, AmmError::ZeroBalance);
+        // an underfunded vault fails the claim instead of paying out part of it
+        require!(self.reward_vault.amount >= amount, AmmError::InsufficientBalance);
+
+        let config_key = self.config.key();
+        let seeds = &[&b"farm"[..], config_key.as_ref(), &[self.farm.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: self.reward_vault.to_account_info(),
+            to: self.user_reward.to_account_info(),
+            authority: self.farm.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds);
+        transfer(ctx, amount)?;
+
+        self.position.pending[index] = 0;
+        Ok(())
+    }
+}
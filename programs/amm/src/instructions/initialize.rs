@@ -1,6 +1,10 @@
-use anchor_lang::{prelude::*, solana_program::address_lookup_table::state::ProgramState};
-use anchor_spl::{associated_token::AssociatedToken, token::{Mint, Token, TokenAccount}};
-use crate::state::Config;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{create_metadata_accounts_v3, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3, Metadata, MetadataAccount},
+    token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer},
+};
+use crate::{error::AmmError, math, state::{Config, LpPosition}, LP_SYMBOL, MAX_LP_DECIMALS, MAX_NAME_LENGTH, MINIMUM_LIQUIDITY};
 
 // this instruction is for the initializer (whoever starts the amm pool and sets the rule)
 /*
@@ -12,11 +16,13 @@ use crate::state::Config;
     - config
     - vault_x
     - vault_y
-    - the three accounts
+    - lp_metadata, metadata_x, metadata_y (metaplex metadata of the lp mint and of the pair)
+    - initializer_x, initializer_y, initializer_lp, locked_lp, position (only to seed initial liquidity)
+    - the three accounts, token metadata program, rent
 */
 
 #[derive(Accounts)]
-#[instruction(seed:u64)]
+#[instruction(seed: u64, fee: u16, authority: Option<Pubkey>, emergency_delay: i64, lp_decimals: Option<u8>)]
 pub struct Initialize<'info>{
 
     #[account(mut)]
@@ -30,7 +36,7 @@ pub struct Initialize<'info>{
         payer = initializer,
         seeds = [b"lp",config.key().as_ref()],
         bump,
-        mint::decimals = 6,
+        mint::decimals = lp_decimals.unwrap_or(Config::default_lp_decimals(mint_x.decimals, mint_y.decimals)),
         mint::authority = config,
     )]
     pub mint_lp: Account<'info,Mint>, // lp tokens to be given to the users
@@ -59,15 +65,85 @@ pub struct Initialize<'info>{
         associated_token::authority = config
     )]
     pub vault_y: Account<'info,TokenAccount>,
+
+    /// CHECK: created by the token metadata program, the address is checked through the seeds
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_lp.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub lp_metadata: UncheckedAccount<'info>,
+
+    // metadata of the pair, only read to name the lp token, pass them when the mints have one
+    #[account(
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_x.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata_x: Option<Box<Account<'info, MetadataAccount>>>,
+
+    #[account(
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_y.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata_y: Option<Box<Account<'info, MetadataAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = mint_x,
+    )]
+    pub initializer_x: Option<Box<Account<'info, TokenAccount>>>, // pays initial_x, signed for by the initializer
+
+    #[account(
+        mut,
+        token::mint = mint_y,
+    )]
+    pub initializer_y: Option<Box<Account<'info, TokenAccount>>>, // pays initial_y
+
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint = mint_lp,
+        associated_token::authority = initializer,
+    )]
+    pub initializer_lp: Option<Box<Account<'info, TokenAccount>>>, // receives the initial lp less MINIMUM_LIQUIDITY
+
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint = mint_lp,
+        associated_token::authority = config,
+    )]
+    pub locked_lp: Option<Box<Account<'info, TokenAccount>>>, // holds MINIMUM_LIQUIDITY, only close_pool burns it
+
+    #[account(
+        init,
+        payer = initializer,
+        space = 8 + LpPosition::INIT_SPACE,
+        seeds = [b"position", config.key().as_ref(), initializer.key().as_ref()],
+        bump
+    )]
+    pub position: Option<Box<Account<'info, LpPosition>>>, // the initializer's position, same as a first deposit would create
     
     pub system_program: Program<'info,System>,
     pub token_program: Program<'info,Token>,
     pub associated_token_program: Program<'info,AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 impl <'info> Initialize<'info> {
 
-    pub fn init(&mut self, seed:u64,authority: Option<Pubkey>, fee:u16, bumps: &InitializeBumps ) -> Result<()>{
+    pub fn init(&mut self, seed:u64,authority: Option<Pubkey>, fee:u16, emergency_delay: i64, lp_decimals: Option<u8>, bumps: &InitializeBumps ) -> Result<()>{
+
+        require!(emergency_delay >= 0, AmmError::InvalidAmount);
+        // a 100% fee would leave nothing to swap, swap_in can't even quote it
+        require!((fee as u64) < math::FEE_DENOMINATOR, AmmError::InvalidFee);
+        // same value the mint_lp constraint used
+        let lp_decimals = lp_decimals.unwrap_or(Config::default_lp_decimals(self.mint_x.decimals, self.mint_y.decimals));
+        require!(lp_decimals <= MAX_LP_DECIMALS, AmmError::InvalidPrecision);
         
         self.config.set_inner(Config { 
             seed, 
@@ -77,9 +153,138 @@ impl <'info> Initialize<'info> {
             fee, 
             locked: false, 
             config_bump: bumps.config, 
-            lp_bump: bumps.mint_lp 
+            lp_bump: bumps.mint_lp,
+            locked_at: 0,
+            emergency_delay,
+            permissioned: false,
+            hook_program: None,
+            fee_growth_x: 0,
+            fee_growth_y: 0,
+            lp_decimals,
+            price_x_cumulative: 0,
+            price_y_cumulative: 0,
+            price_updated_at: Clock::get()?.unix_timestamp,
+            max_swap_price_move_bps: 0,
+            max_slot_price_move_bps: 0,
+            pause_on_price_move: false,
+            slot_start: 0,
+            slot_start_price: 0,
+            max_swap_in_bps: 0,
+            deposit_cap: 0,
+            user_deposit_cap: 0,
+            reserve_x: 0,
+            reserve_y: 0,
         });
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    // a pool initialized without liquidity must not create the accounts only seeding needs, the initializer would pay their rent for nothing
+    pub fn check_unseeded(&self) -> Result<()> {
+        require!(
+            self.initializer_lp.is_none() && self.locked_lp.is_none() && self.position.is_none(),
+            AmmError::UnusedLiquidityAccounts
+        );
+        Ok(())
+    }
+
+    // seeds the new pool with initial_x and initial_y in the same instruction that creates it, so nobody can get a
+    // deposit in first and pick the price. The initializer gets the geometric mean of the two in lp, less
+    // MINIMUM_LIQUIDITY which stays locked in locked_lp until close_pool burns it.
+    pub fn seed_liquidity(&mut self, initial_x: u64, initial_y: u64, bumps: &InitializeBumps) -> Result<()> {
+        require!(initial_x > 0 && initial_y > 0, AmmError::InvalidAmount);
+        let lp = math::initial_lp(initial_x, initial_y)?;
+        require!(lp > MINIMUM_LIQUIDITY, AmmError::LiquidityLessThanMinimum);
+        let user_lp = lp - MINIMUM_LIQUIDITY;
+
+        let (Some(initializer_x), Some(initializer_y), Some(initializer_lp), Some(locked_lp), Some(position)) = (
+            &self.initializer_x,
+            &self.initializer_y,
+            &self.initializer_lp,
+            &self.locked_lp,
+            &mut self.position,
+        ) else {
+            return err!(AmmError::MissingLiquidityAccounts);
+        };
+
+        for (from, to, amount) in [
+            (initializer_x.to_account_info(), self.vault_x.to_account_info(), initial_x),
+            (initializer_y.to_account_info(), self.vault_y.to_account_info(), initial_y),
+        ] {
+            let cpi_accounts = Transfer { from, to, authority: self.initializer.to_account_info() };
+            transfer(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), amount)?;
+        }
+        self.config.add_reserves(initial_x, initial_y)?;
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+        for (to, amount) in [
+            (initializer_lp.to_account_info(), user_lp),
+            (locked_lp.to_account_info(), MINIMUM_LIQUIDITY),
+        ] {
+            let cpi_accounts = MintTo {
+                mint: self.mint_lp.to_account_info(),
+                to,
+                authority: self.config.to_account_info(),
+            };
+            mint_to(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)?;
+        }
+
+        position.config = self.config.key();
+        position.user = self.initializer.key();
+        // anchor fills in the bump whenever position is passed, and it was matched above, so this can't trip
+        position.bump = bumps.position.ok_or(AmmError::MissingLiquidityAccounts)?;
+        position.settle(&self.config, 0, user_lp)?;
+        position.deposited = user_lp;
+        Ok(())
+    }
+
+    // gives mint_lp a name like "AMM LP SOL-USDC" so wallets don't show an unknown token
+    // config stays the update authority, the pool authority can change the uri later through update_lp_metadata
+    pub fn create_lp_metadata(&mut self) -> Result<()> {
+        let symbol_x = Self::pair_symbol(&self.metadata_x, &self.mint_x.key());
+        let symbol_y = Self::pair_symbol(&self.metadata_y, &self.mint_y.key());
+
+        let mut name = format!("AMM LP {}-{}", symbol_x, symbol_y);
+        name.truncate(MAX_NAME_LENGTH);
+
+        let cpi_accounts = CreateMetadataAccountsV3 {
+            metadata: self.lp_metadata.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            mint_authority: self.config.to_account_info(),
+            payer: self.initializer.to_account_info(),
+            update_authority: self.config.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            rent: self.rent.to_account_info(),
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let ctx = CpiContext::new_with_signer(self.token_metadata_program.to_account_info(), cpi_accounts, signer_seeds);
+
+        create_metadata_accounts_v3(
+            ctx,
+            DataV2 {
+                name,
+                symbol: LP_SYMBOL.to_string(),
+                uri: String::new(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true, // mutable so the uri can be set later
+            true,
+            None,
+        )
+    }
+
+    // symbol from the mint's metadata, or the first characters of the mint address when it has none
+    pub fn pair_symbol(metadata: &Option<Box<Account<'info, MetadataAccount>>>, mint: &Pubkey) -> String {
+        match metadata {
+            Some(metadata) => metadata.symbol.trim_matches(char::from(0)).trim().to_string(),
+            None => mint.to_string()[..4].to_string(),
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer, CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{error::AmmError, math, Config, LimitOrder, WhitelistEntry};
+
+// limit orders resting against the pool: the owner escrows the input, any keeper fills it once the
+// curve pays at least min_out, and the owner can cancel it any time before that
+
+/*
+    accounts in the context struct:
+    - owner
+    - mint_in, mint_out
+    - config
+    - limit_order, escrow
+    - owner_in, owner_out
+    - whitelist_entry (optional)
+    - the three programs
+*/
+#[derive(Accounts)]
+#[instruction(id: u64, is_x: bool)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        constraint = mint_in.key() == if is_x { config.mint_x } else { config.mint_y } @ AmmError::InvalidToken
+    )]
+    pub mint_in: Account<'info, Mint>,
+
+    #[account(
+        constraint = mint_out.key() == if is_x { config.mint_y } else { config.mint_x } @ AmmError::InvalidToken
+    )]
+    pub mint_out: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LimitOrder::INIT_SPACE,
+        seeds = [b"limit_order", config.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = limit_order,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+    )]
+    pub owner_in: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed, // created up front so the keeper filling the order never pays for it
+        payer = owner,
+        associated_token::mint = mint_out,
+        associated_token::authority = owner,
+    )]
+    pub owner_out: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), owner.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // only needed on permissioned pools
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceLimitOrder<'info> {
+    // amount is escrowed in full, amount - bounty gets swapped and bounty goes to the keeper
+    pub fn place_limit_order(&mut self, id: u64, is_x: bool, amount: u64, min_out: u64, bounty: u64, bumps: &PlaceLimitOrderBumps) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount > bounty && min_out > 0, AmmError::InvalidAmount);
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+        // fills happen without the owner signing, so a hook would never see who is trading
+        require!(self.config.hook_program.is_none(), AmmError::HookedPool);
+
+        self.limit_order.set_inner(LimitOrder {
+            config: self.config.key(),
+            owner: self.owner.key(),
+            id,
+            is_x,
+            amount,
+            min_out,
+            bounty,
+            bump: bumps.limit_order,
+        });
+
+        let cpi_accounts = Transfer {
+            from: self.owner_in.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        transfer(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), amount)
+    }
+}
+
+/*
+    accounts in the context struct:
+    - keeper
+    - owner (only receives the rent back)
+    - mint_in, mint_out, mint_lp
+    - config
+    - limit_order, escrow
+    - vault_in, vault_out
+    - owner_out, keeper_in
+    - whitelist_entry (optional)
+    - token program
+*/
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    /// CHECK: checked against limit_order.owner, only receives tokens and rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        address = if limit_order.is_x { config.mint_x } else { config.mint_y } @ AmmError::InvalidToken
+    )]
+    pub mint_in: Account<'info, Mint>,
+
+    #[account(
+        address = if limit_order.is_x { config.mint_y } else { config.mint_x } @ AmmError::InvalidToken
+    )]
+    pub mint_out: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    #[account(
+        mut, // fee growth and the price oracle get updated like on a swap
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut, // closed by the fill, not when the circuit breaker stops it
+        has_one = config,
+        has_one = owner,
+        seeds = [b"limit_order", config.key().as_ref(), owner.key().as_ref(), limit_order.id.to_le_bytes().as_ref()],
+        bump = limit_order.bump
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = limit_order,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = config,
+    )]
+    pub vault_in: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_out,
+        associated_token::authority = config,
+    )]
+    pub vault_out: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_out,
+        associated_token::authority = owner,
+    )]
+    pub owner_out: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint_in,
+    )]
+    pub keeper_in: Account<'info, TokenAccount>, // any account of the input mint, receives the bounty
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), owner.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // the owner's, only needed on permissioned pools
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> FillLimitOrder<'info> {
+    pub fn fill_limit_order(&mut self) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // checked again at fill time, the pool may have gained a hook or lost the owner's entry since the order was placed
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+        require!(self.config.hook_program.is_none(), AmmError::HookedPool);
+
+        let is_x = self.limit_order.is_x;
+        let bounty = self.limit_order.bounty;
+        // anyone can send tokens to the escrow, they get sold with the order so it can still be closed
+        let amount_in = self.escrow.amount - bounty;
+
+        let (reserve_in, reserve_out) = self.config.reserves_for(is_x);
+        self.config.check_swap_size(amount_in, reserve_in)?;
+        let res = math::swap_out(reserve_in, reserve_out, amount_in, self.config.fee)?;
+        require!(res.amount_out >= self.limit_order.min_out, AmmError::LimitNotReached);
+
+        // the same circuit breaker as swap, when it locks the pool the order stays open
+        let config = self.config.key();
+        if self.config.check_circuit_breaker(config, is_x, amount_in, res.amount_out)? {
+            return Ok(());
+        }
+
+        let (reserve_x, reserve_y) = (self.config.reserve_x, self.config.reserve_y);
+        self.config.accumulate_price(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
+        self.config.accrue_swap_fee(is_x, res.fee, self.mint_lp.supply)?;
+        self.config.record_swap(is_x, amount_in, res.amount_out)?;
+
+        self.transfer_from_escrow(self.vault_in.to_account_info(), amount_in)?;
+        if bounty > 0 {
+            self.transfer_from_escrow(self.keeper_in.to_account_info(), bounty)?;
+        }
+        self.pay_owner(res.amount_out)?;
+        self.close_escrow()?;
+        self.limit_order.close(self.owner.to_account_info())
+    }
+
+    pub fn pay_owner(&self, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: self.vault_out.to_account_info(),
+            to: self.owner_out.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)
+    }
+
+    pub fn transfer_from_escrow(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to,
+            authority: self.limit_order.to_account_info(),
+        };
+
+        let config = self.config.key();
+        let owner = self.owner.key();
+        let id = self.limit_order.id.to_le_bytes();
+        let seeds = &[&b"limit_order"[..], config.as_ref(), owner.as_ref(), &id, &[self.limit_order.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)
+    }
+
+    pub fn close_escrow(&self) -> Result<()> {
+        let cpi_accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.limit_order.to_account_info(),
+        };
+
+        let config = self.config.key();
+        let owner = self.owner.key();
+        let id = self.limit_order.id.to_le_bytes();
+        let seeds = &[&b"limit_order"[..], config.as_ref(), owner.as_ref(), &id, &[self.limit_order.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        close_account(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds))
+    }
+}
+
+/*
+    accounts in the context struct:
+    - owner
+    - mint_in
+    - config
+    - limit_order, escrow
+    - owner_in
+    - the three programs
+*/
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        address = escrow.mint @ AmmError::InvalidToken
+    )]
+    pub mint_in: Account<'info, Mint>,
+
+    /// CHECK: only its key is used, checked against limit_order.config, so orders can be cancelled after close_pool
+    pub config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = config,
+        has_one = owner,
+        seeds = [b"limit_order", config.key().as_ref(), owner.key().as_ref(), limit_order.id.to_le_bytes().as_ref()],
+        bump = limit_order.bump
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = limit_order,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+    )]
+    pub owner_in: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelLimitOrder<'info> {
+    // works on locked and closed pools too, the escrow never touches the vaults
+    pub fn cancel_limit_order(&mut self) -> Result<()> {
+        let config = self.config.key();
+        let owner = self.owner.key();
+        let id = self.limit_order.id.to_le_bytes();
+        let seeds = &[&b"limit_order"[..], config.as_ref(), owner.as_ref(), &id, &[self.limit_order.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.owner_in.to_account_info(),
+            authority: self.limit_order.to_account_info(),
+        };
+        transfer(
+            CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds),
+            self.escrow.amount,
+        )?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.limit_order.to_account_info(),
+        };
+        close_account(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds))
+    }
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, Config, WhitelistEntry};
+
+// these instructions let the authority manage who can use a permissioned pool
+/*
+    accounts required:
+    - authority
+    - config
+    - whitelist_entry
+    - system program
+*/
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct AddToWhitelist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + WhitelistEntry::INIT_SPACE,
+        seeds = [b"whitelist", config.key().as_ref(), user.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddToWhitelist<'info> {
+    pub fn add_to_whitelist(&mut self, user: Pubkey, bumps: &AddToWhitelistBumps) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+
+        self.whitelist_entry.set_inner(WhitelistEntry {
+            config: self.config.key(),
+            user,
+            bump: bumps.whitelist_entry,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromWhitelist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = authority, // rent goes back to the authority who paid for the entry
+        has_one = config,
+        seeds = [b"whitelist", config.key().as_ref(), whitelist_entry.user.as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+}
+
+impl<'info> RemoveFromWhitelist<'info> {
+    pub fn remove_from_whitelist(&mut self) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{
+        mpl_token_metadata::types::DataV2, update_metadata_accounts_v2, Metadata, MetadataAccount,
+        UpdateMetadataAccountsV2,
+    },
+    token::Mint,
+};
+
+use crate::{error::AmmError, Config};
+
+// this instruction lets the pool authority point the lp token metadata at a new uri
+/*
+    accounts required:
+    - authority
+    - config (update authority of the metadata)
+    - mint_lp
+    - lp_metadata
+    - token metadata program
+*/
+#[derive(Accounts)]
+pub struct UpdateLpMetadata<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), mint_lp.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub lp_metadata: Account<'info, MetadataAccount>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+impl<'info> UpdateLpMetadata<'info> {
+    pub fn update_lp_metadata(&mut self, uri: String) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+
+        // name and symbol stay as they were, only the uri changes
+        let data = DataV2 {
+            name: self.lp_metadata.name.trim_matches(char::from(0)).to_string(),
+            symbol: self.lp_metadata.symbol.trim_matches(char::from(0)).to_string(),
+            uri,
+            seller_fee_basis_points: self.lp_metadata.seller_fee_basis_points,
+            creators: self.lp_metadata.creators.clone(),
+            collection: self.lp_metadata.collection.clone(),
+            uses: self.lp_metadata.uses.clone(),
+        };
+
+        let cpi_accounts = UpdateMetadataAccountsV2 {
+            metadata: self.lp_metadata.to_account_info(),
+            update_authority: self.config.to_account_info(),
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let ctx = CpiContext::new_with_signer(self.token_metadata_program.to_account_info(), cpi_accounts, signer_seeds);
+
+        update_metadata_accounts_v2(ctx, None, Some(data), None, None)
+    }
+}
@@ -11,4 +11,28 @@ pub mod withdraw;
 pub use withdraw::*;
 
 pub mod update;
-pub use update::*;
\ No newline at end of file
+pub use update::*;
+
+pub mod close;
+pub use close::*;
+
+pub mod manage_whitelist;
+pub use manage_whitelist::*;
+
+pub mod farming;
+pub use farming::*;
+
+pub mod claim_fees;
+pub use claim_fees::*;
+
+pub mod metadata;
+pub use metadata::*;
+
+pub mod limit_orders;
+pub use limit_orders::*;
+
+pub mod twap_orders;
+pub use twap_orders::*;
+
+pub mod reserves;
+pub use reserves::*;
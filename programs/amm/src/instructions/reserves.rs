@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+
+use crate::{error::AmmError, Config};
+
+// these instructions let the authority deal with tokens sent straight to the vaults, outside of the pool's reserves
+/*
+    accounts required:
+    - authority
+    - mint_x, mint_y
+    - config
+    - vault_x, vault_y
+    - recipient_x, recipient_y (skim only, receive the excess)
+    - token program (skim only)
+*/
+#[derive(Accounts)]
+pub struct Skim<'info> {
+    pub authority: Signer<'info>,
+
+    pub mint_x: Account<'info, Mint>,
+    pub mint_y: Account<'info, Mint>,
+
+    #[account(
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint_x,
+    )]
+    pub recipient_x: Account<'info, TokenAccount>, // any x account the authority picks
+
+    #[account(
+        mut,
+        token::mint = mint_y,
+    )]
+    pub recipient_y: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+    pub authority: Signer<'info>,
+
+    pub mint_x: Account<'info, Mint>,
+    pub mint_y: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        has_one = mint_x,
+        has_one = mint_y,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config
+    )]
+    pub vault_x: Account<'info, TokenAccount>,
+
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config
+    )]
+    pub vault_y: Account<'info, TokenAccount>,
+}
+
+/*
+    - the excess of a vault is whatever it holds above its reserve, the reserves only move through the program
+    - skim leaves the reserves as they are and pays the excess out, so the pool looks like the donation never happened
+    - sync keeps the tokens and adds them to the reserves, every lp token gets the donation's share of them
+*/
+impl<'info> Skim<'info> {
+    pub fn skim(&mut self) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+
+        let excess_x = self.vault_x.amount.saturating_sub(self.config.reserve_x);
+        let excess_y = self.vault_y.amount.saturating_sub(self.config.reserve_y);
+        require!(excess_x > 0 || excess_y > 0, AmmError::ZeroBalance);
+
+        self.pay_out(true, excess_x)?;
+        self.pay_out(false, excess_y)
+    }
+
+    pub fn pay_out(&self, is_x: bool, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to) = match is_x {
+            true => (self.vault_x.to_account_info(), self.recipient_x.to_account_info()),
+            false => (self.vault_y.to_account_info(), self.recipient_y.to_account_info()),
+        };
+
+        let cpi_accounts = Transfer {
+            from,
+            to,
+            authority: self.config.to_account_info(),
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)
+    }
+}
+
+impl<'info> SyncReserves<'info> {
+    pub fn sync(&mut self) -> Result<()> {
+        require!(self.config.authority == Some(self.authority.key()), AmmError::InvalidAuthority);
+
+        let excess_x = self.vault_x.amount.saturating_sub(self.config.reserve_x);
+        let excess_y = self.vault_y.amount.saturating_sub(self.config.reserve_y);
+        require!(excess_x > 0 || excess_y > 0, AmmError::ZeroBalance);
+
+        // the price is about to jump, the oracle gets the old one up to now first
+        let (reserve_x, reserve_y) = (self.config.reserve_x, self.config.reserve_y);
+        self.config.accumulate_price(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
+        self.config.add_reserves(excess_x, excess_y)
+    }
+}
@@ -1,11 +1,17 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{transfer, Mint, Token, TokenAccount, Transfer, TransferChecked}, token_2022::spl_token_2022::extension::cpi_guard::CpiGuard,
+    token::{transfer, Mint, Token, TokenAccount, Transfer},
 };
-use constant_product_curve::{ConstantProduct,LiquidityPair};
 
-use crate::{error::AmmError, state::Config};
+use crate::{
+    delegate::check_spender,
+    error::AmmError,
+    hook::{invoke_hook, HookAction, HookArgs, HookStage},
+    math,
+    native::{close_wsol, native_side, open_wsol},
+    state::{Config, WhitelistEntry},
+};
 
 // this instruction is for users, in order to swap their tokens 
 /*
@@ -14,10 +20,13 @@ use crate::{error::AmmError, state::Config};
     - mint_x, mint_y, mint_lp
     - config
     - vault_x, vault_y,
-    - user_x, user_y
+    - user_x, user_y (optional, only the ones the swap pays from or into)
+    - source, recipient (optional)
+    - wsol (optional, with wrap_sol)
     - three instructions
 */
 #[derive(Accounts)]
+#[instruction(amount: u64, is_x: bool)]
 pub struct Swap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -26,10 +35,12 @@ pub struct Swap<'info> {
     #[account(
         mut,
         seeds = [b"lp",config.key().as_ref()],
-        bump = config.lp_bump
+        bump = config.lp_bump,
+        mint::decimals = config.lp_decimals
     )]
     pub mint_lp: Account<'info, Mint>,
     #[account(
+        mut, // fee growth gets updated on every swap
         has_one = mint_x, // here has_one puts the check that this mint_x is the same one as mentioned in the config account struct
         has_one = mint_y,
         seeds =[b"config",config.seed.to_le_bytes().as_ref()],
@@ -49,18 +60,46 @@ pub struct Swap<'info> {
     )]
     pub vault_y: Account<'info, TokenAccount>, //ata for mint_y
     #[account(
-        mut,
+        init_if_needed, // created on demand for first time buyers, paid by the user
+        payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
     )]
-    pub user_x: Account<'info, TokenAccount>, //ata for mint_x for user
+    pub user_x: Option<Account<'info, TokenAccount>>, //ata for mint_x for user, left out when source, recipient or wrap_sol replaces it
 
     #[account(
-        mut,
+        init_if_needed, // created on demand for first time buyers, paid by the user
+        payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
     )]
-    pub user_y: Account<'info, TokenAccount>, //ata for mint_y for user
+    pub user_y: Option<Account<'info, TokenAccount>>, //ata for mint_y for user, left out when source, recipient or wrap_sol replaces it
+
+    #[account(
+        mut,
+        token::mint = if is_x { mint_x.key() } else { mint_y.key() },
+    )]
+    pub source: Option<Account<'info, TokenAccount>>, // sold from instead of user_x/user_y, user has to own it or be its delegate
+
+    #[account(mut)]
+    pub recipient: Option<Account<'info, TokenAccount>>, // receives the output instead of user_x/user_y, only its mint is checked
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), user.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // only needed on permissioned pools
+
+    /// CHECK: only used when config.hook_program is set, the key is checked against it in invoke_hook
+    pub hook_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: created and closed by this instruction with wrap_sol, see native.rs
+    #[account(
+        mut,
+        seeds = [b"wsol", user.key().as_ref()],
+        bump
+    )]
+    pub wsol: Option<UncheckedAccount<'info>>,
 
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -69,45 +108,123 @@ pub struct Swap<'info> {
 
 impl<'info> Swap<'info> {
     
-    pub fn swap(&mut self, amount: u64, is_x:bool , min:u64) -> Result<()>{
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap(&mut self, amount: u64, is_x:bool , min:u64, expiration: Option<i64>, wrap_sol: bool, remaining_accounts: &[AccountInfo<'info>], bumps: &SwapBumps) -> Result<()>{
         // here min is the minimum amount of tokens the user expects in return, this helps us to prevent user from taking losses due to slippage
+        // expiration is an optional unix timestamp, a swap landing after it would execute at a stale price so we reject it
+        // wrap_sol trades the wSOL side as plain SOL, see native.rs
         
-        require!(self.config.locked==false,AmmError::PoolLocked);
+        require!(!self.config.locked, AmmError::PoolLocked);
         require!(amount>0, AmmError::InvalidAmount);
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+        if let Some(expiration) = expiration {
+            require!(Clock::get()?.unix_timestamp <= expiration, AmmError::OfferExpired);
+        }
 
-        // This creates a constant product curve (x × y = k)
-        let mut curve = ConstantProduct::init(
-            self.vault_x.amount,
-            self.vault_y.amount,
-            self.mint_lp.supply,
-            self.config.fee,
-            None
-        ).map_err(|_| AmmError::CurveError)?;
-
-        // Determines which token is being sold (X or Y)
-        let pair = match is_x { 
-            true => LiquidityPair::X,
-            false => LiquidityPair::Y,
+        let (amount_x, amount_y) = match is_x {
+            true => (amount, min),
+            false => (min, amount),
         };
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, HookArgs {
+            stage: HookStage::Before,
+            action: HookAction::Swap,
+            amount,
+            is_x,
+            amount_x,
+            amount_y,
+        })?;
+
+        // Determines which reserve is being sold into (X or Y), tokens sent straight to the vaults don't count
+        let (reserve_in, reserve_out) = self.config.reserves_for(is_x);
+        self.config.check_swap_size(amount, reserve_in)?;
+
+        // a source owned by someone else works when they approved user as delegate for at least amount
+        if let Some(source) = &self.source {
+            check_spender(source, &self.user.key(), amount)?;
+        }
+
+        // the recipient can be anyone's account, it only has to hold the token being bought
+        if let Some(recipient) = &self.recipient {
+            let mint_out = match is_x {
+                true => self.mint_y.key(),
+                false => self.mint_x.key(),
+            };
+            require!(recipient.mint == mint_out, AmmError::InvalidToken);
+        }
 
         // Calculates the swap using the constant product formula, min parameter provides slippage protection
-        let res = curve.swap(pair, amount, min).map_err(|_| AmmError::SwapError)?;
+        let res = math::swap_out(reserve_in, reserve_out, amount, self.config.fee)?;
+
+        require!(res.amount_out != 0, AmmError::InvalidAmount);
+        require!(res.amount_out >= min, AmmError::SlippageExceeded);
+
+        // circuit breaker, a swap moving the price too far reverts or locks the pool without trading, see Config
+        let config = self.config.key();
+        if self.config.check_circuit_breaker(config, is_x, res.amount_in, res.amount_out)? {
+            return Ok(());
+        }
 
-        require!(res.deposit != 0 && res.withdraw != 0, AmmError::InvalidAmount);
+        // the price oracle records the price up to now, before this swap changes it
+        let (reserve_x, reserve_y) = (self.config.reserve_x, self.config.reserve_y);
+        self.config.accumulate_price(reserve_x, reserve_y, Clock::get()?.unix_timestamp)?;
+
+        // the fee is paid in the token being sold, spread it over every lp token
+        self.config.accrue_swap_fee(is_x, res.fee, self.mint_lp.supply)?;
+        self.config.record_swap(is_x, res.amount_in, res.amount_out)?;
+
+        // with wrap_sol, selling the wSOL side comes straight out of the user's lamports and buying it pays out SOL,
+        // unless a source or recipient replaces the user's side
+        let wrapped = match wrap_sol {
+            true => Some(native_side(&self.mint_x.key(), &self.mint_y.key())?),
+            false => None,
+        };
+        let wrapped = wrapped.filter(|native_x| match *native_x == is_x {
+            true => self.source.is_none(),
+            false => self.recipient.is_none(),
+        });
+        if let Some(native_x) = wrapped {
+            let native_mint = if native_x { &self.mint_x } else { &self.mint_y };
+            let amount = if native_x == is_x { res.amount_in } else { 0 };
+            open_wsol(&self.user, &self.wsol, bumps.wsol, native_mint, &self.system_program, &self.token_program, amount)?;
+        }
 
         // Transfers tokens from user to vault (what they're selling)
-        self.deposit_tokens_being_sold(is_x, res.deposit)?;
+        self.deposit_tokens_being_sold(is_x, res.amount_in, wrapped == Some(is_x))?;
         // Transfers tokens from vault to user (what they're buying)
-        self.withdraw_tokens_being_bought(is_x, res.withdraw)?;
+        self.withdraw_tokens_being_bought(is_x, res.amount_out, wrapped == Some(!is_x))?;
 
+        let (amount_x, amount_y) = match is_x {
+            true => (res.amount_in, res.amount_out),
+            false => (res.amount_out, res.amount_in),
+        };
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, HookArgs {
+            stage: HookStage::After,
+            action: HookAction::Swap,
+            amount,
+            is_x,
+            amount_x,
+            amount_y,
+        })?;
+
+        // the wSOL bought gets unwrapped into the user's wallet, with the rent of the temporary account
+        if wrapped.is_some() {
+            close_wsol(&self.user, &self.wsol, &self.token_program)?;
+        }
         Ok(())
     }
 
-    pub fn deposit_tokens_being_sold(&mut self, is_x:bool, amount: u64)->Result<()>{
+    // from_wsol: the tokens sold come out of the temporary wSOL account
+    pub fn deposit_tokens_being_sold(&mut self, is_x:bool, amount: u64, from_wsol: bool)->Result<()>{
 
         let (from,to) = match is_x{
-            true => (self.user_x.to_account_info(), self.vault_x.to_account_info()),
-            false => (self.user_y.to_account_info(), self.vault_y.to_account_info()),
+            true => (&self.user_x, self.vault_x.to_account_info()),
+            false => (&self.user_y, self.vault_y.to_account_info()),
+        };
+        let from = match (&self.source, &self.wsol, from) {
+            (Some(source), _, _) => source.to_account_info(),
+            (None, Some(wsol), _) if from_wsol => wsol.to_account_info(),
+            (None, _, Some(from)) => from.to_account_info(),
+            _ => return err!(AmmError::MissingTokenAccount),
         };
 
         let cpi_program = self.token_program.to_account_info();
@@ -123,13 +240,20 @@ impl<'info> Swap<'info> {
         Ok(())
     }
 
-    pub fn withdraw_tokens_being_bought(&mut self, is_x: bool, amount: u64) -> Result<()> {
+    // to_wsol: the tokens bought go to the temporary wSOL account
+    pub fn withdraw_tokens_being_bought(&mut self, is_x: bool, amount: u64, to_wsol: bool) -> Result<()> {
         
         // If is_x is true (user sold X), they now buy/withdraw Y.
         // If is_x is false (user sold Y), they now buy/withdraw X.
         let (from, to) = match is_x {
-            true => (self.vault_y.to_account_info(), self.user_y.to_account_info()),
-            false => (self.vault_x.to_account_info(), self.user_x.to_account_info()),
+            true => (self.vault_y.to_account_info(), &self.user_y),
+            false => (self.vault_x.to_account_info(), &self.user_x),
+        };
+        let to = match (&self.recipient, &self.wsol, to) {
+            (Some(recipient), _, _) => recipient.to_account_info(),
+            (None, Some(wsol), _) if to_wsol => wsol.to_account_info(),
+            (None, _, Some(to)) => to.to_account_info(),
+            _ => return err!(AmmError::MissingTokenAccount),
         };
     
         let cpi_program = self.token_program.to_account_info();
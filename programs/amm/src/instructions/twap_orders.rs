@@ -0,0 +1,408 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, transfer, CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{error::AmmError, math, Config, TwapOrder, WhitelistEntry};
+
+// twap orders split a large sell into fixed slices that anyone can crank, one per interval,
+// each one bounded by the pool's time weighted price since the previous slice
+
+/*
+    accounts in the context struct:
+    - owner
+    - mint_in, mint_out
+    - config
+    - twap_order, escrow
+    - owner_in, owner_out
+    - whitelist_entry (optional)
+    - the three programs
+*/
+#[derive(Accounts)]
+#[instruction(id: u64, is_x: bool)]
+pub struct PlaceTwapOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        constraint = mint_in.key() == if is_x { config.mint_x } else { config.mint_y } @ AmmError::InvalidToken
+    )]
+    pub mint_in: Account<'info, Mint>,
+
+    #[account(
+        constraint = mint_out.key() == if is_x { config.mint_y } else { config.mint_x } @ AmmError::InvalidToken
+    )]
+    pub mint_out: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + TwapOrder::INIT_SPACE,
+        seeds = [b"twap_order", config.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub twap_order: Account<'info, TwapOrder>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = twap_order,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+    )]
+    pub owner_in: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed, // created up front so whoever cranks the order never pays for it
+        payer = owner,
+        associated_token::mint = mint_out,
+        associated_token::authority = owner,
+    )]
+    pub owner_out: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), owner.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // only needed on permissioned pools
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceTwapOrder<'info> {
+    // escrows amount, to be sold slice_amount at a time, one slice per interval seconds
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_twap_order(
+        &mut self,
+        id: u64,
+        is_x: bool,
+        amount: u64,
+        slice_amount: u64,
+        interval: i64,
+        max_slippage_bps: u16,
+        bumps: &PlaceTwapOrderBumps,
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(slice_amount > 0 && slice_amount <= amount, AmmError::InvalidAmount);
+        require!(interval > 0 && max_slippage_bps as u64 <= math::FEE_DENOMINATOR, AmmError::InvalidAmount);
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+        // slices run without the owner signing, so a hook would never see who is trading
+        require!(self.config.hook_program.is_none(), AmmError::HookedPool);
+
+        let now = Clock::get()?.unix_timestamp;
+        let (price_x_cumulative, price_y_cumulative) =
+            self.config.price_cumulative_at(self.config.reserve_x, self.config.reserve_y, now)?;
+
+        self.twap_order.set_inner(TwapOrder {
+            config: self.config.key(),
+            owner: self.owner.key(),
+            id,
+            is_x,
+            slice_amount,
+            remaining: amount,
+            interval,
+            max_slippage_bps,
+            last_executed: now,
+            last_price_cumulative: if is_x { price_x_cumulative } else { price_y_cumulative },
+            bump: bumps.twap_order,
+        });
+
+        let cpi_accounts = Transfer {
+            from: self.owner_in.to_account_info(),
+            to: self.escrow.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        transfer(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), amount)
+    }
+}
+
+/*
+    accounts in the context struct:
+    - cranker
+    - owner (receives the output, and the rent once the order is done)
+    - mint_in, mint_out, mint_lp
+    - config
+    - twap_order, escrow
+    - vault_in, vault_out
+    - owner_out
+    - whitelist_entry (optional)
+    - token program
+*/
+#[derive(Accounts)]
+pub struct ExecuteTwapSlice<'info> {
+    pub cranker: Signer<'info>,
+
+    /// CHECK: checked against twap_order.owner, only receives tokens and rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        address = if twap_order.is_x { config.mint_x } else { config.mint_y } @ AmmError::InvalidToken
+    )]
+    pub mint_in: Account<'info, Mint>,
+
+    #[account(
+        address = if twap_order.is_x { config.mint_y } else { config.mint_x } @ AmmError::InvalidToken
+    )]
+    pub mint_out: Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Account<'info, Mint>,
+
+    #[account(
+        mut, // fee growth and the price oracle get updated like on a swap
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        has_one = config,
+        has_one = owner,
+        seeds = [b"twap_order", config.key().as_ref(), owner.key().as_ref(), twap_order.id.to_le_bytes().as_ref()],
+        bump = twap_order.bump
+    )]
+    pub twap_order: Account<'info, TwapOrder>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = twap_order,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = config,
+    )]
+    pub vault_in: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_out,
+        associated_token::authority = config,
+    )]
+    pub vault_out: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_out,
+        associated_token::authority = owner,
+    )]
+    pub owner_out: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), owner.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // the owner's, only needed on permissioned pools
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> ExecuteTwapSlice<'info> {
+    pub fn execute_twap_slice(&mut self) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // checked again on every slice, the pool may have gained a hook or lost the owner's entry since the order was placed
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+        require!(self.config.hook_program.is_none(), AmmError::HookedPool);
+
+        let now = Clock::get()?.unix_timestamp;
+        let elapsed = now - self.twap_order.last_executed;
+        require!(elapsed >= self.twap_order.interval, AmmError::TwapIntervalNotElapsed);
+
+        let is_x = self.twap_order.is_x;
+        let (reserve_x, reserve_y) = (self.config.reserve_x, self.config.reserve_y);
+
+        // average price of the input token since the last slice, from the pool's own oracle
+        let (price_x_cumulative, price_y_cumulative) = self.config.price_cumulative_at(reserve_x, reserve_y, now)?;
+        let price_cumulative = if is_x { price_x_cumulative } else { price_y_cumulative };
+        let twap = price_cumulative.wrapping_sub(self.twap_order.last_price_cumulative) / elapsed as u128;
+        // the pool had no reserves for the whole window, there is no price to bound the slice with
+        require!(twap > 0, AmmError::SlippageExceeded);
+
+        // the last slice sells the whole escrow, anything sent to it included, so it can be closed
+        let last = self.twap_order.slice_amount >= self.twap_order.remaining;
+        let amount = match last {
+            true => self.escrow.amount,
+            false => self.twap_order.slice_amount,
+        };
+        let min_out = math::min_out_at_price(amount, twap, self.twap_order.max_slippage_bps)?;
+        let (reserve_in, reserve_out) = self.config.reserves_for(is_x);
+        self.config.check_swap_size(amount, reserve_in)?;
+        let res = math::swap_out(reserve_in, reserve_out, amount, self.config.fee)?;
+        require!(res.amount_out >= min_out, AmmError::SlippageExceeded);
+
+        // the same circuit breaker as swap, when it locks the pool the slice can run again once it is unlocked
+        let config = self.config.key();
+        if self.config.check_circuit_breaker(config, is_x, amount, res.amount_out)? {
+            return Ok(());
+        }
+
+        self.config.accumulate_price(reserve_x, reserve_y, now)?;
+        self.config.accrue_swap_fee(is_x, res.fee, self.mint_lp.supply)?;
+        self.config.record_swap(is_x, amount, res.amount_out)?;
+
+        self.sell_from_escrow(amount)?;
+        self.pay_owner(res.amount_out)?;
+
+        self.twap_order.remaining = match last {
+            true => 0,
+            false => self.twap_order.remaining - amount,
+        };
+        self.twap_order.last_executed = now;
+        self.twap_order.last_price_cumulative = price_cumulative;
+
+        // the last slice closes the order, rent goes back to the owner
+        if last {
+            self.close_escrow()?;
+            self.twap_order.close(self.owner.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    pub fn sell_from_escrow(&self, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.vault_in.to_account_info(),
+            authority: self.twap_order.to_account_info(),
+        };
+
+        let config = self.config.key();
+        let owner = self.owner.key();
+        let id = self.twap_order.id.to_le_bytes();
+        let seeds = &[&b"twap_order"[..], config.as_ref(), owner.as_ref(), &id, &[self.twap_order.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)
+    }
+
+    pub fn pay_owner(&self, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: self.vault_out.to_account_info(),
+            to: self.owner_out.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+
+        let seeds = &[&b"config"[..], &self.config.seed.to_le_bytes(), &[self.config.config_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds), amount)
+    }
+
+    pub fn close_escrow(&self) -> Result<()> {
+        let cpi_accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.twap_order.to_account_info(),
+        };
+
+        let config = self.config.key();
+        let owner = self.owner.key();
+        let id = self.twap_order.id.to_le_bytes();
+        let seeds = &[&b"twap_order"[..], config.as_ref(), owner.as_ref(), &id, &[self.twap_order.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        close_account(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds))
+    }
+}
+
+/*
+    accounts in the context struct:
+    - owner
+    - mint_in
+    - config
+    - twap_order, escrow
+    - owner_in
+    - the three programs
+*/
+#[derive(Accounts)]
+pub struct CancelTwapOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        address = escrow.mint @ AmmError::InvalidToken
+    )]
+    pub mint_in: Account<'info, Mint>,
+
+    /// CHECK: only its key is used, checked against twap_order.config, so orders can be cancelled after close_pool
+    pub config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = config,
+        has_one = owner,
+        seeds = [b"twap_order", config.key().as_ref(), owner.key().as_ref(), twap_order.id.to_le_bytes().as_ref()],
+        bump = twap_order.bump
+    )]
+    pub twap_order: Account<'info, TwapOrder>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = twap_order,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+    )]
+    pub owner_in: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelTwapOrder<'info> {
+    // returns whatever has not been sold yet, works on locked and closed pools too
+    pub fn cancel_twap_order(&mut self) -> Result<()> {
+        let config = self.config.key();
+        let owner = self.owner.key();
+        let id = self.twap_order.id.to_le_bytes();
+        let seeds = &[&b"twap_order"[..], config.as_ref(), owner.as_ref(), &id, &[self.twap_order.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: self.escrow.to_account_info(),
+            to: self.owner_in.to_account_info(),
+            authority: self.twap_order.to_account_info(),
+        };
+        transfer(
+            CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds),
+            self.escrow.amount,
+        )?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.twap_order.to_account_info(),
+        };
+        close_account(CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer_seeds))
+    }
+}
@@ -1,8 +1,8 @@
 use anchor_lang::prelude::*;
 
-use crate::{error::AmmError, Config};
+use crate::{error::AmmError, math::FEE_DENOMINATOR, Config};
 
-// this instruction can be used to lock or unlock amm pools
+// this instruction can be used to lock or unlock amm pools, to switch the whitelist on and off, or to tune the caps and the circuit breaker
 /* 
     accounts required:
     - user
@@ -27,6 +27,7 @@ impl<'info> Update<'info> {
         require!(self.config.authority == Some(self.user.key()), AmmError::InvalidAuthority);
 
         self.config.locked = true;
+        self.config.locked_at = Clock::get()?.unix_timestamp;
         Ok(())
     }
 
@@ -36,6 +37,42 @@ impl<'info> Update<'info> {
         require!(self.config.authority == Some(self.user.key()), AmmError::InvalidAuthority);
 
         self.config.locked = false;
+        self.config.locked_at = 0;
+        Ok(())
+    }
+
+    pub fn set_permissioned(&mut self, permissioned: bool) -> Result<()> {
+        require!(self.config.authority == Some(self.user.key()), AmmError::InvalidAuthority);
+
+        self.config.permissioned = permissioned;
+        Ok(())
+    }
+
+    pub fn set_hook(&mut self, hook_program: Option<Pubkey>) -> Result<()> {
+        require!(self.config.authority == Some(self.user.key()), AmmError::InvalidAuthority);
+
+        self.config.hook_program = hook_program;
+        Ok(())
+    }
+
+    // 0 turns a cap off, see Config for what each one counts
+    pub fn set_caps(&mut self, max_swap_in_bps: u16, deposit_cap: u64, user_deposit_cap: u64) -> Result<()> {
+        require!(self.config.authority == Some(self.user.key()), AmmError::InvalidAuthority);
+        require!(max_swap_in_bps as u64 <= FEE_DENOMINATOR, AmmError::InvalidAmount);
+
+        self.config.max_swap_in_bps = max_swap_in_bps;
+        self.config.deposit_cap = deposit_cap;
+        self.config.user_deposit_cap = user_deposit_cap;
+        Ok(())
+    }
+
+    // 0 turns a limit off, see Config for how the two limits are measured
+    pub fn set_circuit_breaker(&mut self, max_swap_price_move_bps: u16, max_slot_price_move_bps: u16, pause_on_price_move: bool) -> Result<()> {
+        require!(self.config.authority == Some(self.user.key()), AmmError::InvalidAuthority);
+
+        self.config.max_swap_price_move_bps = max_swap_price_move_bps;
+        self.config.max_slot_price_move_bps = max_slot_price_move_bps;
+        self.config.pause_on_price_move = pause_on_price_move;
         Ok(())
     }
 }
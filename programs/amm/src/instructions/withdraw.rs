@@ -3,9 +3,11 @@ use anchor_spl::{
     associated_token::AssociatedToken,
     token::{ burn, transfer, Burn, Mint, Token, TokenAccount, Transfer },
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::{ error::AmmError, Config };
+use crate::{ error::AmmError, Config, LpPosition, WhitelistEntry };
+use crate::hook::{ invoke_hook, HookAction, HookArgs, HookStage };
+use crate::math;
+use crate::native::{close_wsol, native_side, open_wsol};
 
 // this is helpful for liquidity providers in order to withdraw their tokens
 
@@ -15,7 +17,9 @@ use crate::{ error::AmmError, Config };
     - mint_x, mint_y, mint_lp
     - config
     - vault_x, vault_y
-    - user_x, user_y, user_lp
+    - user_x, user_y (optional, only the ones the withdrawal pays into), user_lp
+    - recipient_x, recipient_y (optional)
+    - wsol (optional, with wrap_sol)
     - the three accounts
 */
 
@@ -28,6 +32,7 @@ pub struct Withdraw<'info> {
     pub mint_y: Account<'info, Mint>,
 
     #[account(
+        mut, // reserves shrink with the withdrawal
         has_one = mint_x,
         has_one = mint_y,
         seeds = [b"config", &config.seed.to_le_bytes().as_ref()],
@@ -37,8 +42,9 @@ pub struct Withdraw<'info> {
 
     #[account(
         mut,
-        seeds = [b"lp", config.key().as_ref()], 
-        bump
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::decimals = config.lp_decimals
     )]
     pub mint_lp: Account<'info, Mint>,
 
@@ -57,18 +63,20 @@ pub struct Withdraw<'info> {
     pub vault_y: Account<'info, TokenAccount>,
 
     #[account(
-        mut,
+        init_if_needed, // created on demand, paid by the user
+        payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user
     )]
-    pub user_x: Account<'info, TokenAccount>,
+    pub user_x: Option<Account<'info, TokenAccount>>, // left out when recipient_x or wrap_sol replaces it
 
     #[account(
-        mut,
+        init_if_needed, // created on demand, paid by the user
+        payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user
     )]
-    pub user_y: Account<'info, TokenAccount>,
+    pub user_y: Option<Account<'info, TokenAccount>>, // left out when recipient_y or wrap_sol replaces it
 
     #[account(
         mut,
@@ -77,6 +85,42 @@ pub struct Withdraw<'info> {
     )]
     pub user_lp: Account<'info, TokenAccount>,
 
+    #[account(
+        mut,
+        seeds = [b"position", config.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Option<Account<'info, LpPosition>>, // lps who received their tokens by transfer may not have one
+
+    #[account(
+        mut,
+        token::mint = mint_x,
+    )]
+    pub recipient_x: Option<Account<'info, TokenAccount>>, // receives x instead of user_x, only its mint is checked
+
+    #[account(
+        mut,
+        token::mint = mint_y,
+    )]
+    pub recipient_y: Option<Account<'info, TokenAccount>>, // receives y instead of user_y, only its mint is checked
+
+    #[account(
+        seeds = [b"whitelist", config.key().as_ref(), user.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>, // only needed on permissioned pools
+
+    /// CHECK: only used when config.hook_program is set, the key is checked against it in invoke_hook
+    pub hook_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: created and closed by this instruction with wrap_sol, see native.rs
+    #[account(
+        mut,
+        seeds = [b"wsol", user.key().as_ref()],
+        bump
+    )]
+    pub wsol: Option<UncheckedAccount<'info>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -88,35 +132,126 @@ pub struct Withdraw<'info> {
 */
 
 impl<'info> Withdraw<'info> {
-    pub fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64, expiration: Option<i64>, wrap_sol: bool, remaining_accounts: &[AccountInfo<'info>], bumps: &WithdrawBumps) -> Result<()> {
         // amount: this is the amount of lp tokens the user is ready to trade for (i.e. that would be burned by us)
-        require!(self.config.locked == false, AmmError::PoolLocked);
+        // expiration: optional unix timestamp after which the withdrawal is rejected
+        // wrap_sol: pay the wSOL side out as SOL through a temporary wSOL account, see native.rs
+        require!(!self.config.locked, AmmError::PoolLocked);
         require!(amount > 0, AmmError::InvalidAmount);
+        require!(!self.config.permissioned || self.whitelist_entry.is_some(), AmmError::NotWhitelisted);
+        if let Some(expiration) = expiration {
+            require!(Clock::get()?.unix_timestamp <= expiration, AmmError::OfferExpired);
+        }
+
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, HookArgs {
+            stage: HookStage::Before,
+            action: HookAction::Withdraw,
+            amount,
+            is_x: true,
+            amount_x: min_x,
+            amount_y: min_y,
+        })?;
 
-        let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
-            self.vault_x.amount,
-            self.vault_y.amount,
+        let amounts = math::withdraw_amounts(
+            self.config.reserve_x,
+            self.config.reserve_y,
             self.mint_lp.supply,
             amount,
-            6
-        ).map_err(|_| AmmError::InvalidPrecision)?;
+        )?;
 
         // if amount withdrawn 
         require!(amounts.x >= min_x && amounts.y >= min_y, AmmError::SlippageExceeded);
         require!(self.user_lp.amount >= amount, AmmError::InsufficientBalance);
 
-        self.withdraw_token(true, amounts.x)?;   // Withdraw X tokens
-        self.withdraw_token(false, amounts.y)?;  // Withdraw Y tokens
+        // the native side is paid out as SOL, unless a recipient replaces the user's account for it
+        let wrapped = match wrap_sol {
+            true => Some(native_side(&self.mint_x.key(), &self.mint_y.key())?),
+            false => None,
+        };
+        let wrapped = wrapped.filter(|native_x| match native_x {
+            true => self.recipient_x.is_none(),
+            false => self.recipient_y.is_none(),
+        });
+        if let Some(native_x) = wrapped {
+            let native_mint = if native_x { &self.mint_x } else { &self.mint_y };
+            open_wsol(&self.user, &self.wsol, bumps.wsol, native_mint, &self.system_program, &self.token_program, 0)?;
+        }
+
+        self.withdraw_token(true, amounts.x, wrapped == Some(true))?;   // Withdraw X tokens
+        self.withdraw_token(false, amounts.y, wrapped == Some(false))?;  // Withdraw Y tokens
+        self.config.sub_reserves(amounts.x, amounts.y)?;
+        self.settle_position(amount)?;
         self.burn(amount)?;
+
+        invoke_hook(&self.config, &self.hook_program, &self.user, remaining_accounts, HookArgs {
+            stage: HookStage::After,
+            action: HookAction::Withdraw,
+            amount,
+            is_x: true,
+            amount_x: amounts.x,
+            amount_y: amounts.y,
+        })?;
+
+        if wrapped.is_some() {
+            close_wsol(&self.user, &self.wsol, &self.token_program)?;
+        }
         Ok(())
     }
 
-    // transfer tokens from the vault ata to the user ata
-    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
+    // strictly proportional exit, no fees and no hook, only for pools that have been locked
+    // for longer than config.emergency_delay. Open pools go through the regular withdraw
+    pub fn emergency_withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+        require!(self.user_lp.amount >= amount, AmmError::InsufficientBalance);
+        // can't trip after the balance check, a user holding amount > 0 lp means the supply isn't 0. Kept for withdraw_amounts
+        require!(self.mint_lp.supply > 0, AmmError::NoLiquidityInPool);
+        require!(self.config.locked, AmmError::EmergencyWithdrawUnavailable);
+
+        let now = Clock::get()?.unix_timestamp;
+        let opens_at = self.config.locked_at
+            .checked_add(self.config.emergency_delay)
+            .ok_or(AmmError::Overflow)?;
+        require!(now >= opens_at, AmmError::EmergencyWithdrawUnavailable);
 
-        let (from, to) = match is_x {
-            true => (self.vault_x.to_account_info(), self.user_x.to_account_info()),
-            false => (self.vault_y.to_account_info(), self.user_y.to_account_info()),
+        // share of each vault owned by the lp tokens being burned
+        let amounts = math::withdraw_amounts(
+            self.config.reserve_x,
+            self.config.reserve_y,
+            self.mint_lp.supply,
+            amount,
+        )?;
+
+        require!(amounts.x >= min_x && amounts.y >= min_y, AmmError::SlippageExceeded);
+
+        self.withdraw_token(true, amounts.x, false)?;
+        self.withdraw_token(false, amounts.y, false)?;
+        self.config.sub_reserves(amounts.x, amounts.y)?;
+        self.settle_position(amount)?;
+        self.burn(amount)
+    }
+
+    // settles fees earned so far before amount lp tokens get burned
+    pub fn settle_position(&mut self, amount: u64) -> Result<()> {
+        if let Some(position) = self.position.as_mut() {
+            position.settle(&self.config, self.user_lp.amount, self.user_lp.amount - amount)?;
+            position.deposited = position.deposited.saturating_sub(amount);
+        }
+        Ok(())
+    }
+
+    // transfer tokens from the vault ata to the user ata, or to the temporary wSOL account with to_wsol
+    pub fn withdraw_token(&mut self, is_x: bool, amount: u64, to_wsol: bool) -> Result<()> {
+
+        let (from, to, recipient) = match is_x {
+            true => (self.vault_x.to_account_info(), &self.user_x, &self.recipient_x),
+            false => (self.vault_y.to_account_info(), &self.user_y, &self.recipient_y),
+        };
+        let to = match (recipient, &self.wsol, to) {
+            (Some(recipient), _, _) => recipient.to_account_info(),
+            (None, Some(wsol), _) if to_wsol => wsol.to_account_info(),
+            (None, _, Some(to)) => to.to_account_info(),
+            _ => return err!(AmmError::MissingTokenAccount),
         };
 
         let cpi_program = self.token_program.to_account_info();
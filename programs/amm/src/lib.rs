@@ -1,8 +1,13 @@
 #![allow(deprecated)]
 #![allow(unexpected_cfgs)]
 pub mod constants;
+pub mod delegate;
 pub mod error;
+pub mod events;
+pub mod hook;
 pub mod instructions;
+pub mod math;
+pub mod native;
 pub mod state;
 
 use anchor_lang::prelude::*;
@@ -17,40 +22,70 @@ declare_id!("CdG5T7mPU7fENrgMaPEpfqEBxFS7czM12BrjS49EAcKv");
 pub mod amm {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         ctx: Context<Initialize>,
         seed: u64,
         fee: u16,
         authority: Option<Pubkey>,
+        emergency_delay: i64,
+        lp_decimals: Option<u8>,
+        initial_x: u64,
+        initial_y: u64,
     ) -> Result<()> {
-        ctx.accounts.init(seed, authority, fee, &ctx.bumps)
+        ctx.accounts.init(seed, authority, fee, emergency_delay, lp_decimals, &ctx.bumps)?;
+        ctx.accounts.create_lp_metadata()?;
+        // both 0 leaves the pool empty for the first deposit
+        match initial_x > 0 || initial_y > 0 {
+            true => ctx.accounts.seed_liquidity(initial_x, initial_y, &ctx.bumps),
+            false => ctx.accounts.check_unseeded(),
+        }
     }
 
-    pub fn deposit(
-        ctx: Context<Deposit>,
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
         amount: u64,
         max_x: u64,
         max_y: u64,
+        expiration: Option<i64>,
+        wrap_sol: bool,
     ) -> Result<()> {
-        ctx.accounts.deposit(amount, max_x, max_y)
+        ctx.accounts.deposit(amount, max_x, max_y, expiration, wrap_sol, ctx.remaining_accounts, &ctx.bumps)
     }
 
-    pub fn swap(
-        ctx: Context<Swap>,
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
         amount: u64,
         is_x: bool,
         min: u64,
+        expiration: Option<i64>,
+        wrap_sol: bool,
     ) -> Result<()> {
-        ctx.accounts.swap(amount, is_x, min)
+        ctx.accounts.swap(amount, is_x, min, expiration, wrap_sol, ctx.remaining_accounts, &ctx.bumps)
     }
 
-    pub fn withdraw(
+    pub fn withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: Option<i64>,
+        wrap_sol: bool,
+    ) -> Result<()> {
+        ctx.accounts.withdraw(amount, min_x, min_y, expiration, wrap_sol, ctx.remaining_accounts, &ctx.bumps)
+    }
+
+    pub fn claim_fees<'info>(ctx: Context<'_, '_, '_, 'info, ClaimFees<'info>>) -> Result<()> {
+        ctx.accounts.claim_fees(ctx.remaining_accounts)
+    }
+
+    pub fn emergency_withdraw(
         ctx: Context<Withdraw>,
         amount: u64,
         min_x: u64,
         min_y: u64,
     ) -> Result<()> {
-        ctx.accounts.withdraw(amount, min_x, min_y)
+        ctx.accounts.emergency_withdraw(amount, min_x, min_y)
     }
 
     pub fn lock(ctx: Context<Update>) -> Result<()> {
@@ -60,4 +95,117 @@ pub mod amm {
     pub fn unlock(ctx: Context<Update>) -> Result<()> {
         ctx.accounts.unlock()
     }
+
+    pub fn set_permissioned(ctx: Context<Update>, permissioned: bool) -> Result<()> {
+        ctx.accounts.set_permissioned(permissioned)
+    }
+
+    pub fn add_to_whitelist(ctx: Context<AddToWhitelist>, user: Pubkey) -> Result<()> {
+        ctx.accounts.add_to_whitelist(user, &ctx.bumps)
+    }
+
+    pub fn remove_from_whitelist(ctx: Context<RemoveFromWhitelist>) -> Result<()> {
+        ctx.accounts.remove_from_whitelist()
+    }
+
+    pub fn update_lp_metadata(ctx: Context<UpdateLpMetadata>, uri: String) -> Result<()> {
+        ctx.accounts.update_lp_metadata(uri)
+    }
+
+    pub fn set_hook(ctx: Context<Update>, hook_program: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.set_hook(hook_program)
+    }
+
+    pub fn set_caps(
+        ctx: Context<Update>,
+        max_swap_in_bps: u16,
+        deposit_cap: u64,
+        user_deposit_cap: u64,
+    ) -> Result<()> {
+        ctx.accounts.set_caps(max_swap_in_bps, deposit_cap, user_deposit_cap)
+    }
+
+    pub fn set_circuit_breaker(
+        ctx: Context<Update>,
+        max_swap_price_move_bps: u16,
+        max_slot_price_move_bps: u16,
+        pause_on_price_move: bool,
+    ) -> Result<()> {
+        ctx.accounts.set_circuit_breaker(max_swap_price_move_bps, max_slot_price_move_bps, pause_on_price_move)
+    }
+
+    pub fn skim(ctx: Context<Skim>) -> Result<()> {
+        ctx.accounts.skim()
+    }
+
+    pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+        ctx.accounts.sync()
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        ctx.accounts.close_pool()
+    }
+
+    pub fn initialize_farm(ctx: Context<InitializeFarm>) -> Result<()> {
+        ctx.accounts.init_farm(&ctx.bumps)
+    }
+
+    pub fn add_reward(ctx: Context<AddReward>, rate: u64) -> Result<()> {
+        ctx.accounts.add_reward(rate)
+    }
+
+    pub fn set_reward_rate(ctx: Context<SetRewardRate>, index: u8, rate: u64) -> Result<()> {
+        ctx.accounts.set_reward_rate(index, rate)
+    }
+
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        ctx.accounts.stake(amount, &ctx.bumps)
+    }
+
+    pub fn unstake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        ctx.accounts.unstake(amount)
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>, index: u8) -> Result<()> {
+        ctx.accounts.claim_rewards(index)
+    }
+
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        id: u64,
+        is_x: bool,
+        amount: u64,
+        min_out: u64,
+        bounty: u64,
+    ) -> Result<()> {
+        ctx.accounts.place_limit_order(id, is_x, amount, min_out, bounty, &ctx.bumps)
+    }
+
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>) -> Result<()> {
+        ctx.accounts.fill_limit_order()
+    }
+
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        ctx.accounts.cancel_limit_order()
+    }
+
+    pub fn place_twap_order(
+        ctx: Context<PlaceTwapOrder>,
+        id: u64,
+        is_x: bool,
+        amount: u64,
+        slice_amount: u64,
+        interval: i64,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.place_twap_order(id, is_x, amount, slice_amount, interval, max_slippage_bps, &ctx.bumps)
+    }
+
+    pub fn execute_twap_slice(ctx: Context<ExecuteTwapSlice>) -> Result<()> {
+        ctx.accounts.execute_twap_slice()
+    }
+
+    pub fn cancel_twap_order(ctx: Context<CancelTwapOrder>) -> Result<()> {
+        ctx.accounts.cancel_twap_order()
+    }
 }
\ No newline at end of file
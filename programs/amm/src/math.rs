@@ -0,0 +1,215 @@
+use crate::{error::AmmError, FEE_GROWTH_PRECISION, PRICE_PRECISION};
+
+// pure pool math: no accounts, no cpi, no anchor types beyond AmmError
+// the instructions, amm-client and off-chain simulators all call these, so there is one implementation to audit
+//
+// every intermediate product is done in u128, a u64 * u64 can't overflow it
+//
+// rounding always goes in the pool's favour, so no sequence of operations can pull out more than its share:
+// - swap fee: rounded up, the trader pays at least fee_bps
+// - swap_out: output rounded down
+// - swap_in: required input rounded up
+// - deposit_amounts: tokens taken rounded up
+// - withdraw_amounts: tokens paid out rounded down
+// - lp_for_amount: lp burned rounded up
+// - fee_growth: rounded down, lps are credited at most what was paid in
+// - initial_lp: lp minted for seeding rounded down
+
+pub const FEE_DENOMINATOR: u64 = 10_000; // fees are in basis points
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub amount_after_fee: u64,
+    pub fee: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapAmounts {
+    pub amount_in: u64, // taken from the trader, fee included
+    pub amount_out: u64, // paid to the trader
+    pub fee: u64, // part of amount_in that is the fee, in the input token
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidityAmounts {
+    pub x: u64,
+    pub y: u64,
+}
+
+// a * b / c
+pub fn mul_div(a: u64, b: u64, c: u64, rounding: Rounding) -> Result<u64, AmmError> {
+    if c == 0 {
+        return Err(AmmError::ZeroBalance);
+    }
+
+    let product = a as u128 * b as u128;
+    let result = match rounding {
+        Rounding::Down => product / c as u128,
+        Rounding::Up => product.div_ceil(c as u128),
+    };
+
+    u64::try_from(result).map_err(|_| AmmError::Overflow)
+}
+
+// splits amount into the fee (fee_bps of it) and what is left
+pub fn split_fee(amount: u64, fee_bps: u16, rounding: Rounding) -> Result<FeeSplit, AmmError> {
+    if fee_bps as u64 > FEE_DENOMINATOR {
+        return Err(AmmError::InvalidFee);
+    }
+
+    let fee = mul_div(amount, fee_bps as u64, FEE_DENOMINATOR, rounding)?;
+    Ok(FeeSplit {
+        amount_after_fee: amount - fee,
+        fee,
+    })
+}
+
+// exact input: selling amount_in into the pool, the fee comes off the input before it hits the curve
+pub fn swap_out(reserve_in: u64, reserve_out: u64, amount_in: u64, fee_bps: u16) -> Result<SwapAmounts, AmmError> {
+    if amount_in == 0 {
+        return Err(AmmError::InvalidAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(AmmError::NoLiquidityInPool);
+    }
+
+    let split = split_fee(amount_in, fee_bps, Rounding::Up)?;
+
+    // (reserve_in + in) * (reserve_out - out) = reserve_in * reserve_out
+    let new_reserve_in = reserve_in.checked_add(split.amount_after_fee).ok_or(AmmError::Overflow)?;
+    let amount_out = mul_div(reserve_out, split.amount_after_fee, new_reserve_in, Rounding::Down)?;
+
+    Ok(SwapAmounts {
+        amount_in,
+        amount_out,
+        fee: split.fee,
+    })
+}
+
+// exact output: what has to be sold to get amount_out out of the pool
+pub fn swap_in(reserve_in: u64, reserve_out: u64, amount_out: u64, fee_bps: u16) -> Result<SwapAmounts, AmmError> {
+    if amount_out == 0 {
+        return Err(AmmError::InvalidAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(AmmError::NoLiquidityInPool);
+    }
+    if amount_out >= reserve_out {
+        return Err(AmmError::InsufficientBalance);
+    }
+    if fee_bps as u64 >= FEE_DENOMINATOR {
+        return Err(AmmError::InvalidFee);
+    }
+
+    // inverse of swap_out, rounded up so the trader never gets amount_out for less
+    let amount_after_fee = mul_div(reserve_in, amount_out, reserve_out - amount_out, Rounding::Up)?;
+    let amount_in = mul_div(amount_after_fee, FEE_DENOMINATOR, FEE_DENOMINATOR - fee_bps as u64, Rounding::Up)?;
+
+    Ok(SwapAmounts {
+        amount_in,
+        amount_out,
+        fee: amount_in - amount_after_fee,
+    })
+}
+
+// tokens that have to come in to mint lp new lp tokens, proportional to the reserves
+pub fn deposit_amounts(x: u64, y: u64, supply: u64, lp: u64) -> Result<LiquidityAmounts, AmmError> {
+    if lp == 0 {
+        return Err(AmmError::InvalidAmount);
+    }
+    if supply == 0 {
+        return Err(AmmError::NoLiquidityInPool);
+    }
+
+    Ok(LiquidityAmounts {
+        x: mul_div(x, lp, supply, Rounding::Up)?,
+        y: mul_div(y, lp, supply, Rounding::Up)?,
+    })
+}
+
+// tokens paid out for burning lp lp tokens, proportional to the reserves
+pub fn withdraw_amounts(x: u64, y: u64, supply: u64, lp: u64) -> Result<LiquidityAmounts, AmmError> {
+    if lp == 0 {
+        return Err(AmmError::InvalidAmount);
+    }
+    if supply == 0 {
+        return Err(AmmError::NoLiquidityInPool);
+    }
+    if lp > supply {
+        return Err(AmmError::InsufficientBalance);
+    }
+
+    Ok(LiquidityAmounts {
+        x: mul_div(x, lp, supply, Rounding::Down)?,
+        y: mul_div(y, lp, supply, Rounding::Down)?,
+    })
+}
+
+// lp tokens whose share of a vault covers amount, rounded up so the share never comes out short
+pub fn lp_for_amount(amount: u64, vault: u64, supply: u64) -> Result<u64, AmmError> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    if amount > vault {
+        return Err(AmmError::InsufficientBalance);
+    }
+    mul_div(amount, supply, vault, Rounding::Up)
+}
+
+// fee growth per lp token, scaled by FEE_GROWTH_PRECISION, from a fee paid into a pool with supply lp tokens
+pub fn fee_growth(fee: u64, supply: u64) -> Result<u128, AmmError> {
+    if supply == 0 {
+        return Err(AmmError::NoLiquidityInPool);
+    }
+    let scaled = (fee as u128).checked_mul(FEE_GROWTH_PRECISION).ok_or(AmmError::Overflow)?;
+    Ok(scaled / supply as u128)
+}
+
+// lp minted for seeding an empty pool with x and y, the geometric mean so the price doesn't affect it, rounded down
+pub fn initial_lp(x: u64, y: u64) -> Result<u64, AmmError> {
+    let lp = (x as u128 * y as u128).isqrt();
+    u64::try_from(lp).map_err(|_| AmmError::Overflow)
+}
+
+// price of one base token in quote tokens, scaled by PRICE_PRECISION
+pub fn spot_price(reserve_base: u64, reserve_quote: u64) -> Result<u128, AmmError> {
+    if reserve_base == 0 {
+        return Err(AmmError::NoLiquidityInPool);
+    }
+    Ok(reserve_quote as u128 * PRICE_PRECISION / reserve_base as u128)
+}
+
+// least amount_in should buy at price (scaled by PRICE_PRECISION) allowing slippage_bps off it, rounded up
+pub fn min_out_at_price(amount_in: u64, price: u128, slippage_bps: u16) -> Result<u64, AmmError> {
+    if slippage_bps as u64 > FEE_DENOMINATOR {
+        return Err(AmmError::InvalidFee);
+    }
+
+    let at_price = (amount_in as u128)
+        .checked_mul(price)
+        .ok_or(AmmError::Overflow)?
+        / PRICE_PRECISION;
+    let min_out = at_price
+        .checked_mul((FEE_DENOMINATOR - slippage_bps as u64) as u128)
+        .ok_or(AmmError::Overflow)?
+        .div_ceil(FEE_DENOMINATOR as u128);
+    u64::try_from(min_out).map_err(|_| AmmError::Overflow)
+}
+
+// how far price moved away from start, in bps of start, rounded down
+pub fn price_move_bps(start: u128, price: u128) -> Result<u128, AmmError> {
+    if start == 0 {
+        return Err(AmmError::NoLiquidityInPool);
+    }
+    start
+        .abs_diff(price)
+        .checked_mul(FEE_DENOMINATOR as u128)
+        .map(|moved| moved / start)
+        .ok_or(AmmError::Overflow)
+}
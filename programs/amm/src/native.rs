@@ -0,0 +1,111 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer},
+};
+use anchor_spl::token::{
+    close_account, initialize_account3, spl_token::native_mint, CloseAccount, InitializeAccount3, Mint, Token, TokenAccount,
+};
+
+use crate::error::AmmError;
+
+// wrapping for pools that have the native mint (wSOL) on one side, so wallets can trade SOL directly
+/*
+    with wrap_sol set on deposit, swap or withdraw:
+    - the wSOL side goes through a temporary token account at [b"wsol", user], the user's own wSOL ata is never touched
+    - open_wsol creates it holding the lamports going into the pool (nothing for payouts), as wSOL owned by the user
+    - close_wsol closes it into the wallet at the end of the instruction, which unwraps the pool's payout and refunds its rent
+    - the account only exists within one instruction, but its closed shell lives until the end of the transaction,
+      so a transaction can hold only one wrap_sol instruction per user
+*/
+
+pub const WSOL_SEED: &[u8] = b"wsol";
+
+pub fn is_native(mint: &Pubkey) -> bool {
+    *mint == native_mint::ID
+}
+
+// true when x is the native side, false when y is
+pub fn native_side(mint_x: &Pubkey, mint_y: &Pubkey) -> Result<bool> {
+    match (is_native(mint_x), is_native(mint_y)) {
+        (true, _) => Ok(true),
+        (_, true) => Ok(false),
+        _ => err!(AmmError::NoNativeMint),
+    }
+}
+
+// creates the temporary wSOL account funded with amount lamports from the user's wallet
+pub fn open_wsol<'info>(
+    user: &Signer<'info>,
+    wsol: &Option<UncheckedAccount<'info>>,
+    bump: Option<u8>,
+    native_mint: &Account<'info, Mint>,
+    system_program: &Program<'info, System>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let (Some(wsol), Some(bump)) = (wsol, bump) else {
+        return err!(AmmError::MissingWsolAccount);
+    };
+    require!(is_native(&native_mint.key()), AmmError::NoNativeMint);
+
+    let lamports = Rent::get()?
+        .minimum_balance(TokenAccount::LEN)
+        .checked_add(amount)
+        .ok_or(AmmError::Overflow)?;
+    let user_key = user.key();
+    let seeds = &[WSOL_SEED, user_key.as_ref(), &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    // lamports sent to the address beforehand would make create_account fail, so then it is funded, allocated
+    // and assigned step by step. Those lamports end up wrapped too and go to the user on close
+    if wsol.lamports() == 0 {
+        let cpi_accounts = CreateAccount {
+            from: user.to_account_info(),
+            to: wsol.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer_seeds);
+        create_account(ctx, lamports, TokenAccount::LEN as u64, &token_program.key())?;
+    } else {
+        let cpi_accounts = Transfer {
+            from: user.to_account_info(),
+            to: wsol.to_account_info(),
+        };
+        transfer(CpiContext::new(system_program.to_account_info(), cpi_accounts), lamports)?;
+
+        let cpi_accounts = Allocate {
+            account_to_allocate: wsol.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer_seeds);
+        allocate(ctx, TokenAccount::LEN as u64)?;
+
+        let cpi_accounts = Assign {
+            account_to_assign: wsol.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer_seeds);
+        assign(ctx, &token_program.key())?;
+    }
+
+    // native accounts start out holding every lamport above rent as wSOL
+    let cpi_accounts = InitializeAccount3 {
+        account: wsol.to_account_info(),
+        mint: native_mint.to_account_info(),
+        authority: user.to_account_info(),
+    };
+    initialize_account3(CpiContext::new(token_program.to_account_info(), cpi_accounts))
+}
+
+// closes the temporary wSOL account into the user's wallet, whatever it holds comes back as SOL
+pub fn close_wsol<'info>(
+    user: &Signer<'info>,
+    wsol: &Option<UncheckedAccount<'info>>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    // only called after open_wsol succeeded, which already rejected a missing account
+    let wsol = wsol.as_ref().ok_or(AmmError::MissingWsolAccount)?;
+    let cpi_accounts = CloseAccount {
+        account: wsol.to_account_info(),
+        destination: user.to_account_info(),
+        authority: user.to_account_info(),
+    };
+    close_account(CpiContext::new(token_program.to_account_info(), cpi_accounts))
+}
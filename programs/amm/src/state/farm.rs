@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, MAX_REWARDS, REWARD_PRECISION};
+
+#[account]
+#[derive(InitSpace)]
+pub struct Farm {
+    pub config: Pubkey, // pool whose lp tokens are staked here
+    pub total_staked: u64,
+    pub last_update: i64, // last time the accumulators were brought up to date
+    #[max_len(MAX_REWARDS)]
+    pub rewards: Vec<RewardInfo>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct RewardInfo {
+    pub mint: Pubkey,
+    pub rate: u64, // reward tokens emitted per second, shared by all stakers
+    pub acc_per_share: u128, // rewards earned by one staked lp token since the reward was added, scaled by REWARD_PRECISION
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakePosition {
+    pub farm: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub reward_debt: [u128; MAX_REWARDS], // amount * acc_per_share at the last settlement, per reward
+    pub pending: [u64; MAX_REWARDS], // settled but not yet claimed, per reward
+    pub bump: u8,
+}
+
+/*
+- Farm: one per pool, derived from [b"farm", config]. The staked lp tokens sit in the farm's lp ata, and every reward mint has its own farm owned ata that the authority tops up.
+
+- The standard accumulator pattern: for every reward we keep acc_per_share, the total emitted per staked token. A user is owed amount * acc_per_share - reward_debt, and every time their stake changes we settle that into pending and reset reward_debt.
+
+- StakePosition: one per (farm, user), derived from [b"stake", farm, user].
+*/
+
+impl Farm {
+    // brings every reward accumulator up to now, must run before total_staked or a rate changes
+    pub fn update(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_update).max(0) as u128;
+
+        if elapsed > 0 && self.total_staked > 0 {
+            for reward in self.rewards.iter_mut() {
+                let emitted = (reward.rate as u128)
+                    .checked_mul(elapsed)
+                    .and_then(|e| e.checked_mul(REWARD_PRECISION))
+                    .ok_or(AmmError::Overflow)?;
+                reward.acc_per_share = reward
+                    .acc_per_share
+                    .checked_add(emitted / self.total_staked as u128)
+                    .ok_or(AmmError::Overflow)?;
+            }
+        }
+
+        self.last_update = now;
+        Ok(())
+    }
+}
+
+impl StakePosition {
+    // moves everything earned since the last settlement into pending
+    pub fn settle(&mut self, farm: &Farm) -> Result<()> {
+        for (i, reward) in farm.rewards.iter().enumerate() {
+            let accrued = (self.amount as u128)
+                .checked_mul(reward.acc_per_share)
+                .ok_or(AmmError::Overflow)?;
+            // can't underflow, the debt was amount * acc_per_share when amount last changed and acc_per_share only grows
+            let earned = accrued
+                .checked_sub(self.reward_debt[i])
+                .ok_or(AmmError::Underflow)?
+                / REWARD_PRECISION;
+            self.pending[i] = self.pending[i]
+                .checked_add(u64::try_from(earned).map_err(|_| AmmError::Overflow)?)
+                .ok_or(AmmError::Overflow)?;
+            self.reward_debt[i] = accrued;
+        }
+        Ok(())
+    }
+
+    // resets the debt after amount changed, so nothing before this point is counted again
+    pub fn reset_debt(&mut self, farm: &Farm) -> Result<()> {
+        for (i, reward) in farm.rewards.iter().enumerate() {
+            self.reward_debt[i] = (self.amount as u128)
+                .checked_mul(reward.acc_per_share)
+                .ok_or(AmmError::Overflow)?;
+        }
+        Ok(())
+    }
+}
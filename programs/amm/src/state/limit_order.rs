@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct LimitOrder {
+    pub config: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64, // picked by the owner, lets one wallet keep several orders on a pool
+    pub is_x: bool, // sells x for y when true, y for x otherwise, like Swap::swap
+    pub amount: u64, // escrowed input, bounty included
+    pub min_out: u64, // the limit: fills only when amount - bounty buys at least this much
+    pub bounty: u64, // paid in the input token to the keeper that fills the order
+    pub bump: u8,
+}
+
+/*
+- One per (pool, owner, id), derived from [b"limit_order", config, owner, id]. The input tokens sit in the order's own ata (the escrow) until the order is filled or cancelled, both close the order and the escrow back to the owner. Tokens anyone else sends to the escrow are sold along with the order on a fill, so it always empties and closes.
+
+- The limit price is min_out / (amount - bounty). It is checked against what the curve actually pays out, fee and price impact included, so an order can only be filled once the marginal price has moved past the limit, and never fills worse than it.
+
+- fill_limit_order is permissionless. Keepers watch the pool and fill orders as soon as they are in range, the bounty pays for their transaction.
+*/
@@ -1,5 +1,27 @@
 use anchor_lang::prelude::*;
 
+use crate::{
+    error::AmmError,
+    events::CircuitBreakerTripped,
+    math::{self, Rounding, FEE_DENOMINATOR},
+    MAX_LP_DECIMALS,
+};
+
+pub mod whitelist;
+pub use whitelist::*;
+
+pub mod farm;
+pub use farm::*;
+
+pub mod position;
+pub use position::*;
+
+pub mod limit_order;
+pub use limit_order::*;
+
+pub mod twap_order;
+pub use twap_order::*;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config{
@@ -11,6 +33,26 @@ pub struct Config{
     pub locked: bool,
     pub config_bump: u8,
     pub lp_bump: u8,
+    pub locked_at: i64, // unix timestamp of the last lock, 0 while unlocked
+    pub emergency_delay: i64, // seconds a pool must stay locked before emergency withdrawals open up
+    pub permissioned: bool, // when true only whitelisted wallets can swap, deposit or withdraw
+    pub hook_program: Option<Pubkey>, // optional program cpi'd before and after swap, deposit and withdraw
+    pub fee_growth_x: u128, // swap fees paid in x per lp token, ever, scaled by FEE_GROWTH_PRECISION
+    pub fee_growth_y: u128,
+    pub lp_decimals: u8, // decimals of mint_lp, chosen at initialize
+    pub price_x_cumulative: u128, // sum of (y per x) * seconds, scaled by PRICE_PRECISION, wraps around
+    pub price_y_cumulative: u128, // sum of (x per y) * seconds
+    pub price_updated_at: i64, // unix timestamp the accumulators were last brought up to date
+    pub max_swap_price_move_bps: u16, // most a single swap may move the price, 0 is no limit
+    pub max_slot_price_move_bps: u16, // most the price may move within one slot, 0 is no limit
+    pub pause_on_price_move: bool, // lock the pool instead of reverting when a limit is hit
+    pub slot_start: u64, // slot slot_start_price was taken in
+    pub slot_start_price: u128, // y per x before the first swap of slot_start, scaled by PRICE_PRECISION
+    pub max_swap_in_bps: u16, // largest swap input as bps of the reserve it goes into, 0 is no limit
+    pub deposit_cap: u64, // most lp tokens that may ever be outstanding, 0 is no limit
+    pub user_deposit_cap: u64, // most lp tokens one user may hold through their deposits, 0 is no limit
+    pub reserve_x: u64, // x the curve trades against, vault_x can hold more
+    pub reserve_y: u64,
 }
 
 /*
@@ -28,4 +70,173 @@ When None, the pool becomes "unlocked" or decentralized - no single entity can c
 - locked: bool - A safety mechanism that can pause all trading activity. When true, swaps are disabled but liquidity operations might still work.
 
 - config_bump: u8 & lp_bump: u8 - These store the "bump seeds" used to generate PDAs for the config account itself and the LP (liquidity provider) token mint. Storing these saves computation on subsequent operations since you don't need to derive them again.
-*/
\ No newline at end of file
+
+- locked_at: i64 & emergency_delay: i64 - When the pool gets locked we remember the time. Once it has been locked for emergency_delay seconds, LPs can always take a proportional exit through emergency_withdraw, even if the authority key is lost. A delay of 0 opens the emergency exit as soon as the pool is locked, it is never open on an unlocked pool.
+
+- permissioned: bool - Turns the pool into a KYC'd pool. Swap, Deposit and Withdraw then require a WhitelistEntry PDA for the signer, which only the authority can create or close. Emergency withdrawals stay open to every LP holder so funds are never stuck.
+
+- hook_program: Option<Pubkey> - When set, swap, deposit and withdraw cpi into this program before and after moving tokens (see hook.rs for the account and argument layout). The hook can reject the call by returning an error, which is how rewards, rebates or compliance checks get plugged in without forking the amm.
+
+- fee_growth_x: u128 & fee_growth_y: u128 - Every swap adds fee / lp_supply to the accumulator of the token that was sold. Fees still get compounded into the vaults, these only let an LpPosition work out how much of its value came from fees.
+
+- lp_decimals: u8 - Decimals of the lp mint. Either passed to initialize or taken from the pair (the larger of the two mint decimals, capped at MAX_LP_DECIMALS). Every instruction checks mint_lp against it.
+
+- price_x_cumulative: u128, price_y_cumulative: u128 & price_updated_at: i64 - The pool's own price oracle. Before any swap moves the reserves, the spot price that held since price_updated_at is added once per elapsed second. Sampling an accumulator at two times and dividing the difference by the seconds in between gives the time weighted average price over that window, which one swap can't move much. Both directions are kept because the average of x per y is not the inverse of the average of y per x. The sums are meant to wrap, only differences are ever used. Deposits and withdrawals keep the reserve ratio, so they don't need to update them.
+
+- max_swap_price_move_bps: u16, max_slot_price_move_bps: u16 & pause_on_price_move: bool - A circuit breaker against price manipulation and runaway trades. A swap is measured by how far it moves the spot price (y per x) from where it was before the swap, and from slot_start_price. The first swap in a new slot records the price it starts from as slot_start_price, so several small swaps in one slot can't add up past the slot limit. Going over either limit reverts the swap with PriceMoveExceeded, or with pause_on_price_move locks the pool and emits CircuitBreakerTripped until the authority unlocks it. Limit order fills and TWAP slices trade against the same curve and go through the same checks. Only the authority can change these, through set_circuit_breaker.
+
+- slot_start: u64 & slot_start_price: u128 - Bookkeeping for the per slot limit, see above.
+
+- max_swap_in_bps: u16, deposit_cap: u64 & user_deposit_cap: u64 - Guard rails for young pools, set by the authority through set_caps (send it in the same transaction as initialize to launch guarded) and raised as the pool matures. A swap may put at most max_swap_in_bps of the input reserve in. Both deposit caps count lp tokens, which stand for a fixed share of the vaults, so they don't depend on either token's price: deposit_cap limits the lp supply (the pool's total value locked) and user_deposit_cap limits what one user's LpPosition has deposited and not yet withdrawn. Failing any of them gives SwapTooLarge, DepositCapExceeded or UserDepositCapExceeded.
+
+- reserve_x: u64 & reserve_y: u64 - The pool's balances as far as the math goes. Every instruction that moves tokens in or out of the vaults moves these by the same amount, and swaps, deposits, withdrawals and the price oracle only ever read these. Tokens sent straight to a vault are not part of them, so a donation can't shift the price or the value of an lp token by itself. The authority decides what happens to such an excess: skim pays it out to a recipient of their choice, sync adds it to the reserves (and so to the lps). Pools without an authority keep any excess in the vaults, untouched.
+*/
+
+impl Config {
+    // lp decimals used when initialize is not given any
+    pub fn default_lp_decimals(decimals_x: u8, decimals_y: u8) -> u8 {
+        decimals_x.max(decimals_y).min(MAX_LP_DECIMALS)
+    }
+
+    // brings the price accumulators up to now, call before a swap moves the reserves
+    pub fn accumulate_price(&mut self, vault_x: u64, vault_y: u64, now: i64) -> Result<()> {
+        (self.price_x_cumulative, self.price_y_cumulative) = self.price_cumulative_at(vault_x, vault_y, now)?;
+        self.price_updated_at = now;
+        Ok(())
+    }
+
+    // what the accumulators would read at now, given the reserves haven't changed since price_updated_at
+    pub fn price_cumulative_at(&self, vault_x: u64, vault_y: u64, now: i64) -> Result<(u128, u128)> {
+        let elapsed = now.saturating_sub(self.price_updated_at);
+        if elapsed <= 0 || vault_x == 0 || vault_y == 0 {
+            return Ok((self.price_x_cumulative, self.price_y_cumulative));
+        }
+
+        let price_x = math::spot_price(vault_x, vault_y)?;
+        let price_y = math::spot_price(vault_y, vault_x)?;
+        Ok((
+            self.price_x_cumulative.wrapping_add(price_x.wrapping_mul(elapsed as u128)),
+            self.price_y_cumulative.wrapping_add(price_y.wrapping_mul(elapsed as u128)),
+        ))
+    }
+
+    // checks a swap taking the reserves from (vault_x, vault_y) to (new_x, new_y) against the circuit breaker
+    // returns the breach as (price_before, price_after), or None when the swap is within limits
+    pub fn check_price_move(&mut self, vault_x: u64, vault_y: u64, new_x: u64, new_y: u64, slot: u64) -> Result<Option<(u128, u128)>> {
+        let price_before = math::spot_price(vault_x, vault_y)?;
+        if self.slot_start != slot || self.slot_start_price == 0 {
+            self.slot_start = slot;
+            self.slot_start_price = price_before;
+        }
+        if self.max_swap_price_move_bps == 0 && self.max_slot_price_move_bps == 0 {
+            return Ok(None);
+        }
+
+        let price_after = math::spot_price(new_x, new_y)?;
+        let swap_move = math::price_move_bps(price_before, price_after)?;
+        let slot_move = math::price_move_bps(self.slot_start_price, price_after)?;
+
+        let breached = (self.max_swap_price_move_bps != 0 && swap_move > self.max_swap_price_move_bps as u128)
+            || (self.max_slot_price_move_bps != 0 && slot_move > self.max_slot_price_move_bps as u128);
+        Ok(breached.then_some((price_before, price_after)))
+    }
+
+    // runs a trade against the curve, selling amount_in of x (is_x) or y for amount_out, through the circuit breaker.
+    // Past a limit it reverts, or with pause_on_price_move locks the pool and returns true: the caller then has to
+    // return Ok without trading so the lock sticks. config is this account's key, for the event
+    pub fn check_circuit_breaker(&mut self, config: Pubkey, is_x: bool, amount_in: u64, amount_out: u64) -> Result<bool> {
+        let (reserve_x, reserve_y) = (self.reserve_x, self.reserve_y);
+        let (new_x, new_y) = match is_x {
+            true => (reserve_x.checked_add(amount_in), reserve_y.checked_sub(amount_out)),
+            false => (reserve_x.checked_sub(amount_out), reserve_y.checked_add(amount_in)),
+        };
+        let (new_x, new_y) = (new_x.ok_or(AmmError::Overflow)?, new_y.ok_or(AmmError::Overflow)?);
+
+        let clock = Clock::get()?;
+        let Some((price_before, price_after)) = self.check_price_move(reserve_x, reserve_y, new_x, new_y, clock.slot)? else {
+            return Ok(false);
+        };
+        require!(self.pause_on_price_move, AmmError::PriceMoveExceeded);
+
+        self.locked = true;
+        self.locked_at = clock.unix_timestamp;
+        emit!(CircuitBreakerTripped {
+            config,
+            slot: clock.slot,
+            slot_start_price: self.slot_start_price,
+            price_before,
+            price_after,
+        });
+        Ok(true)
+    }
+
+    // a swap of amount into a vault holding reserve_in has to stay under max_swap_in_bps of it
+    pub fn check_swap_size(&self, amount: u64, reserve_in: u64) -> Result<()> {
+        if self.max_swap_in_bps == 0 {
+            return Ok(());
+        }
+        let max_in = math::mul_div(reserve_in, self.max_swap_in_bps as u64, FEE_DENOMINATOR, Rounding::Down)?;
+        require!(amount <= max_in, AmmError::SwapTooLarge);
+        Ok(())
+    }
+
+    // minting amount more lp has to keep the supply and the user's running total under their caps
+    pub fn check_deposit_caps(&self, amount: u64, lp_supply: u64, user_deposited: u64) -> Result<()> {
+        let supply = lp_supply.checked_add(amount).ok_or(AmmError::Overflow)?;
+        require!(self.deposit_cap == 0 || supply <= self.deposit_cap, AmmError::DepositCapExceeded);
+        let deposited = user_deposited.checked_add(amount).ok_or(AmmError::Overflow)?;
+        require!(self.user_deposit_cap == 0 || deposited <= self.user_deposit_cap, AmmError::UserDepositCapExceeded);
+        Ok(())
+    }
+
+    // (reserve_in, reserve_out) for a swap selling x (is_x) or y
+    pub fn reserves_for(&self, is_x: bool) -> (u64, u64) {
+        match is_x {
+            true => (self.reserve_x, self.reserve_y),
+            false => (self.reserve_y, self.reserve_x),
+        }
+    }
+
+    // x and y went into the vaults
+    pub fn add_reserves(&mut self, x: u64, y: u64) -> Result<()> {
+        self.reserve_x = self.reserve_x.checked_add(x).ok_or(AmmError::Overflow)?;
+        self.reserve_y = self.reserve_y.checked_add(y).ok_or(AmmError::Overflow)?;
+        Ok(())
+    }
+
+    // x and y left the vaults
+    pub fn sub_reserves(&mut self, x: u64, y: u64) -> Result<()> {
+        self.reserve_x = self.reserve_x.checked_sub(x).ok_or(AmmError::Overflow)?;
+        self.reserve_y = self.reserve_y.checked_sub(y).ok_or(AmmError::Overflow)?;
+        Ok(())
+    }
+
+    // a swap sold amount_in of x (is_x) or y for amount_out of the other side
+    pub fn record_swap(&mut self, is_x: bool, amount_in: u64, amount_out: u64) -> Result<()> {
+        match is_x {
+            true => {
+                self.add_reserves(amount_in, 0)?;
+                self.sub_reserves(0, amount_out)
+            }
+            false => {
+                self.add_reserves(0, amount_in)?;
+                self.sub_reserves(amount_out, 0)
+            }
+        }
+    }
+
+    // spreads a swap fee, paid in x (is_x) or y, over every lp token
+    pub fn accrue_swap_fee(&mut self, is_x: bool, fee: u64, lp_supply: u64) -> Result<()> {
+        if lp_supply == 0 {
+            return Ok(());
+        }
+
+        let growth = math::fee_growth(fee, lp_supply)?;
+        let fee_growth = match is_x {
+            true => &mut self.fee_growth_x,
+            false => &mut self.fee_growth_y,
+        };
+        *fee_growth = fee_growth.checked_add(growth).ok_or(AmmError::Overflow)?;
+        Ok(())
+    }
+}
\ No newline at end of file
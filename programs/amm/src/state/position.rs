@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::AmmError, Config, FEE_GROWTH_PRECISION};
+
+#[account]
+#[derive(InitSpace)]
+pub struct LpPosition {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    pub lp_amount: u64, // lp balance the position last saw
+    pub fee_growth_x_entry: u128, // config.fee_growth_x at the last settlement
+    pub fee_growth_y_entry: u128,
+    pub fees_owed_x: u64, // settled but not yet claimed
+    pub fees_owed_y: u64,
+    pub deposited: u64, // lp minted by this user's deposits less lp they withdrew, counted against config.user_deposit_cap
+    pub bump: u8,
+}
+
+/*
+- One per (pool, user), derived from [b"position", config, user]. Created on the first deposit.
+
+- Fees earned since the last settlement are lp_amount * (fee_growth - fee_growth_entry). If lp tokens were moved out of the wallet in the meantime we only count the smaller of lp_amount and the current balance, so a position can never claim fees for lp it does not hold.
+
+- deposited goes up with every deposit the user pays for, even when the lp is sent to an lp_recipient, and down with their withdrawals. It never goes below 0, so lp received by transfer and withdrawn only frees up cap.
+*/
+
+impl LpPosition {
+    // fees owed at this point in time, without changing anything. Clients can call this on a fetched position to show earned fees.
+    pub fn earned(&self, config: &Config, lp_balance: u64) -> Result<(u64, u64)> {
+        let lp = self.lp_amount.min(lp_balance) as u128;
+
+        let x = config.fee_growth_x
+            .checked_sub(self.fee_growth_x_entry)
+            .and_then(|g| g.checked_mul(lp))
+            .ok_or(AmmError::Overflow)?
+            / FEE_GROWTH_PRECISION;
+        let y = config.fee_growth_y
+            .checked_sub(self.fee_growth_y_entry)
+            .and_then(|g| g.checked_mul(lp))
+            .ok_or(AmmError::Overflow)?
+            / FEE_GROWTH_PRECISION;
+
+        Ok((
+            self.fees_owed_x.checked_add(u64::try_from(x).map_err(|_| AmmError::Overflow)?).ok_or(AmmError::Overflow)?,
+            self.fees_owed_y.checked_add(u64::try_from(y).map_err(|_| AmmError::Overflow)?).ok_or(AmmError::Overflow)?,
+        ))
+    }
+
+    // folds earned fees into fees_owed and tracks the new lp balance from here on
+    pub fn settle(&mut self, config: &Config, lp_balance: u64, new_lp_amount: u64) -> Result<()> {
+        let (x, y) = self.earned(config, lp_balance)?;
+
+        self.fees_owed_x = x;
+        self.fees_owed_y = y;
+        self.fee_growth_x_entry = config.fee_growth_x;
+        self.fee_growth_y_entry = config.fee_growth_y;
+        self.lp_amount = new_lp_amount;
+        Ok(())
+    }
+}
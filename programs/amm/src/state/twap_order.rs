@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct TwapOrder {
+    pub config: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64, // picked by the owner, lets one wallet keep several orders on a pool
+    pub is_x: bool, // sells x for y when true, y for x otherwise, like Swap::swap
+    pub slice_amount: u64, // sold per slice, the last slice sells whatever is left
+    pub remaining: u64, // still in escrow
+    pub interval: i64, // seconds between slices
+    pub max_slippage_bps: u16, // how far below the pool's twap a slice may fill
+    pub last_executed: i64, // unix timestamp of the last slice, or of placing the order
+    pub last_price_cumulative: u128, // config price accumulator of the input token at last_executed
+    pub bump: u8,
+}
+
+/*
+- One per (pool, owner, id), derived from [b"twap_order", config, owner, id]. The whole amount is escrowed in the order's own ata up front, every slice pays its output straight to the owner's ata.
+
+- Anyone can crank execute_twap_slice, at most once per interval. The first slice can run one interval after placing the order.
+
+- Each slice's slippage limit comes from the pool's own oracle (see Config price_x_cumulative): the time weighted average price since the previous slice, minus max_slippage_bps. A slice right after someone pushed the spot price away from where it has been trading reverts instead of selling into it.
+
+- The order closes itself after the last slice, The last slice sells the whole escrow, tokens someone else sent to it included, cancel_twap_order returns what is left any time before that.
+*/
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct WhitelistEntry {
+    pub config: Pubkey, // pool this entry belongs to
+    pub user: Pubkey, // wallet that is allowed to trade and provide liquidity
+    pub bump: u8,
+}
+
+/*
+- One entry per (pool, wallet) pair, derived from [b"whitelist", config, user]. The entry existing is the approval, so removing a wallet just closes the account.
+- LP tokens themselves stay freely transferable, the classic token program has no transfer hooks. A non whitelisted holder still cannot withdraw through the normal path, only through emergency_withdraw.
+*/
@@ -0,0 +1,63 @@
+#![allow(deprecated)]
+#![allow(unexpected_cfgs)]
+use anchor_lang::prelude::*;
+
+declare_id!("GEfzPwJWg8yam7o7JNq4YUVbFhYMtE2g5az7fWY63ZAB");
+
+// after-stage calls for this amount fail, so the tests can check the amm instruction is rolled back
+// after its tokens already moved
+pub const REJECTED_AMOUNT: u64 = 1_313;
+
+// minimal hook for the amm tests, it logs every call and rejects REJECTED_AMOUNT
+#[program]
+pub mod amm_test_hook {
+    use super::*;
+
+    // the instruction name gives the discriminator amm cpis with, see amm::HOOK_DISCRIMINATOR
+    pub fn amm_hook(_ctx: Context<AmmHook>, args: HookArgs) -> Result<()> {
+        msg!("amm_hook {:?} {:?} {} {} {}", args.stage, args.action, args.amount, args.amount_x, args.amount_y);
+        require!(
+            args.stage == HookStage::Before || args.amount != REJECTED_AMOUNT,
+            HookError::Rejected
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AmmHook<'info> {
+    /// CHECK: the calling pool's config, only logged
+    pub config: UncheckedAccount<'info>,
+    pub user: Signer<'info>,
+}
+
+// mirrors amm::hook, the layout is what amm serializes
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookStage {
+    Before,
+    After,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookAction {
+    Swap,
+    Deposit,
+    Withdraw,
+    ClaimFees,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HookArgs {
+    pub stage: HookStage,
+    pub action: HookAction,
+    pub amount: u64,
+    pub is_x: bool,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[error_code]
+pub enum HookError {
+    #[msg("Rejected by the test hook.")]
+    Rejected,
+}
@@ -0,0 +1,218 @@
+#![allow(dead_code)]
+
+use amm::{error::AmmError, Config};
+use amm_client::{ata, decode_config, instructions, PoolKeys};
+use anchor_lang::{
+    error::{ErrorCode, ERROR_CODE_OFFSET},
+    AccountDeserialize,
+};
+use anchor_spl::{
+    metadata::mpl_token_metadata,
+    token::{spl_token::native_mint, Mint, TokenAccount},
+};
+use litesvm::{types::TransactionResult, LiteSVM};
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_sdk::{
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+// in-process fixtures for the amm program
+//
+// the tests load prebuilt programs, `anchor run litesvm` builds them and runs the suites:
+// - target/deploy/amm.so from `anchor build`
+// - target/deploy/amm_test_hook.so, the hook in tests/amm-test-hook. It sits outside programs/ so anchor never
+//     builds or deploys it, the script builds it with
+//     cargo build-sbf --manifest-path programs/amm/tests/amm-test-hook/Cargo.toml --sbf-out-dir target/deploy
+// - tests/fixtures/mpl_token_metadata.so, initialize cpis into token metadata, dumped once with
+//     solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s programs/amm/tests/fixtures/mpl_token_metadata.so
+// a plain `cargo test` with any of them missing fails every litesvm test naming the file, it doesn't skip them
+
+pub const DECIMALS: u8 = 6;
+pub const SEED: u64 = 42;
+pub const FEE: u16 = 30;
+
+pub struct Fixture {
+    pub svm: LiteSVM,
+    pub authority: Keypair, // pays for everything, owns both mints and the pool
+    pub pool: PoolKeys,
+}
+
+impl Fixture {
+    // programs loaded, both mints created, pool not initialized yet
+    pub fn new() -> Self {
+        let mut svm = LiteSVM::new();
+
+        let root = concat!(env!("CARGO_MANIFEST_DIR"), "/../..");
+        svm.add_program_from_file(amm::ID, format!("{}/target/deploy/amm.so", root))
+            .expect("target/deploy/amm.so missing, run `anchor run litesvm`");
+        svm.add_program_from_file(
+            mpl_token_metadata::ID,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mpl_token_metadata.so"),
+        )
+        .expect("tests/fixtures/mpl_token_metadata.so missing, run `anchor run litesvm`");
+
+        let authority = Keypair::new();
+        svm.airdrop(&authority.pubkey(), 100_000_000_000).unwrap();
+
+        let mint_x = CreateMint::new(&mut svm, &authority).decimals(DECIMALS).send().unwrap();
+        let mint_y = CreateMint::new(&mut svm, &authority).decimals(DECIMALS).send().unwrap();
+
+        Self {
+            svm,
+            authority,
+            pool: PoolKeys::new(SEED, mint_x, mint_y),
+        }
+    }
+
+    // fixture with a pool the fixture's authority controls
+    pub fn with_pool() -> Self {
+        let mut fixture = Self::new();
+        let ix = fixture.initialize_ix(Some(fixture.authority.pubkey()), 0, None);
+        fixture.send(ix, &[]).unwrap();
+        fixture
+    }
+
+    // fixture with a wSOL / y pool the fixture's authority controls, LiteSVM::new comes with the native mint
+    pub fn native() -> Self {
+        let mut fixture = Self::new();
+        fixture.pool = PoolKeys::new(SEED, native_mint::ID, fixture.pool.mint_y);
+        let ix = fixture.initialize_ix(Some(fixture.authority.pubkey()), 0, None);
+        fixture.send(ix, &[]).unwrap();
+        fixture
+    }
+
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.svm.get_account(address).map_or(0, |account| account.lamports)
+    }
+
+    // fixture with a pool whose hook is the amm-test-hook program in tests/amm-test-hook
+    pub fn with_hook() -> Self {
+        let mut fixture = Self::with_pool();
+        let root = concat!(env!("CARGO_MANIFEST_DIR"), "/../..");
+        fixture
+            .svm
+            .add_program_from_file(amm_test_hook::ID, format!("{}/target/deploy/amm_test_hook.so", root))
+            .expect("target/deploy/amm_test_hook.so missing, run `anchor run litesvm`");
+
+        let ix = instructions::set_hook(&fixture.authority.pubkey(), &fixture.pool, Some(amm_test_hook::ID));
+        fixture.send(ix, &[]).unwrap();
+        fixture.pool.hook_program = Some(amm_test_hook::ID);
+        fixture
+    }
+
+    pub fn initialize_ix(&self, authority: Option<Pubkey>, emergency_delay: i64, lp_decimals: Option<u8>) -> Instruction {
+        instructions::initialize(
+            &self.authority.pubkey(),
+            &self.pool,
+            FEE,
+            authority,
+            emergency_delay,
+            lp_decimals,
+            false,
+            false,
+        )
+    }
+
+    // a funded wallet with no token accounts yet
+    pub fn wallet(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+        user
+    }
+
+    // creates owner's ata for mint holding amount
+    pub fn fund(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) {
+        let account = CreateAssociatedTokenAccount::new(&mut self.svm, &self.authority, mint)
+            .owner(owner)
+            .send()
+            .unwrap();
+        if amount > 0 {
+            MintTo::new(&mut self.svm, &self.authority, mint, &account, amount).send().unwrap();
+        }
+    }
+
+    // a funded wallet holding x and y of the pair
+    pub fn user(&mut self, x: u64, y: u64) -> Keypair {
+        let user = self.wallet();
+        let (mint_x, mint_y) = (self.pool.mint_x, self.pool.mint_y);
+        self.fund(&user.pubkey(), &mint_x, x);
+        self.fund(&user.pubkey(), &mint_y, y);
+        user
+    }
+
+    // a user that deposited x and y into an empty pool, receiving lp lp tokens
+    pub fn seeded_lp(&mut self, x: u64, y: u64, lp: u64) -> Keypair {
+        let user = self.user(x, y);
+        let ix = instructions::deposit(&user.pubkey(), &self.pool, lp, x, y, None, false, &[]);
+        self.send(ix, &[&user]).unwrap();
+        user
+    }
+
+    // signs with the authority as fee payer plus signers, and expires the blockhash so identical
+    // transactions can be sent again. The large error is litesvm's FailedTransactionMetadata, passed through as is
+    #[allow(clippy::result_large_err)]
+    pub fn send(&mut self, ix: Instruction, signers: &[&Keypair]) -> TransactionResult {
+        let mut all: Vec<&Keypair> = vec![&self.authority];
+        all.extend_from_slice(signers);
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.authority.pubkey()),
+            &all,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(tx);
+        self.svm.expire_blockhash();
+        result
+    }
+
+    pub fn config(&self) -> Config {
+        decode_config(&self.svm.get_account(&self.pool.config).unwrap().data).unwrap()
+    }
+
+    pub fn balance(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        self.token_account(&ata(owner, mint)).amount
+    }
+
+    pub fn token_account(&self, address: &Pubkey) -> TokenAccount {
+        TokenAccount::try_deserialize(&mut &self.svm.get_account(address).unwrap().data[..]).unwrap()
+    }
+
+    pub fn mint(&self, address: &Pubkey) -> Mint {
+        Mint::try_deserialize(&mut &self.svm.get_account(address).unwrap().data[..]).unwrap()
+    }
+
+    pub fn set_time(&mut self, unix_timestamp: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    pub fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+}
+
+// the transaction failed with exactly this program error
+pub fn assert_amm_error(result: TransactionResult, error: AmmError) {
+    assert_error_code(result, ERROR_CODE_OFFSET + error as u32);
+}
+
+// the transaction failed with exactly this anchor error, e.g. a failed account constraint
+pub fn assert_anchor_error(result: TransactionResult, error: ErrorCode) {
+    assert_error_code(result, error as u32);
+}
+
+pub fn assert_error_code(result: TransactionResult, code: u32) {
+    let failed = result.expect_err("transaction should have failed");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(code)),
+        "logs: {:#?}",
+        failed.meta.logs
+    );
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use amm::{error::AmmError, MAX_REWARDS};
+use amm_client::{decode_farm, decode_stake_position, instructions, stake_pda};
+use common::*;
+use litesvm_token::{CreateMint, MintTo};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const RATE: u64 = 100; // reward tokens per second
+
+// a pool with two lps, a farm started at t = 1_000 and one funded reward emitting RATE
+fn farm() -> (Fixture, Pubkey, Keypair, Keypair) {
+    let mut fixture = Fixture::with_pool();
+    let alice = fixture.seeded_lp(500_000, 2_000_000, 1_000_000);
+    let bob = fixture.user(1_500_000, 6_000_000);
+    let ix = instructions::deposit(&bob.pubkey(), &fixture.pool, 3_000_000, u64::MAX, u64::MAX, None, false, &[]);
+    fixture.send(ix, &[&bob]).unwrap();
+
+    fixture.set_time(1_000);
+    let authority = fixture.authority.pubkey();
+    fixture.send(instructions::initialize_farm(&authority, &fixture.pool), &[]).unwrap();
+
+    let reward_mint = CreateMint::new(&mut fixture.svm, &fixture.authority).decimals(DECIMALS).send().unwrap();
+    fixture.send(instructions::add_reward(&authority, &fixture.pool, &reward_mint, RATE), &[]).unwrap();
+    let vault = instructions::reward_vault(&fixture.pool, &reward_mint);
+    MintTo::new(&mut fixture.svm, &fixture.authority, &reward_mint, &vault, 1_000_000).send().unwrap();
+
+    (fixture, reward_mint, alice, bob)
+}
+
+fn claim(fixture: &mut Fixture, user: &Keypair, reward_mint: &Pubkey) {
+    let ix = instructions::claim_rewards(&user.pubkey(), &fixture.pool, 0, reward_mint);
+    fixture.send(ix, &[user]).unwrap();
+}
+
+#[test]
+fn two_stakers_split_emissions_by_stake_and_time() {
+    let (mut fixture, reward_mint, alice, bob) = farm();
+
+    fixture.send(instructions::stake(&alice.pubkey(), &fixture.pool, 1_000_000), &[&alice]).unwrap();
+    fixture.set_time(1_100);
+    fixture.send(instructions::stake(&bob.pubkey(), &fixture.pool, 3_000_000), &[&bob]).unwrap();
+    fixture.set_time(1_200);
+
+    // alice had the farm to herself for 100s, then a quarter of it for 100s
+    claim(&mut fixture, &alice, &reward_mint);
+    claim(&mut fixture, &bob, &reward_mint);
+    assert_eq!(fixture.balance(&alice.pubkey(), &reward_mint), 100 * RATE + 100 * RATE / 4);
+    assert_eq!(fixture.balance(&bob.pubkey(), &reward_mint), 100 * RATE * 3 / 4);
+
+    // once alice leaves, bob earns everything
+    fixture.send(instructions::unstake(&alice.pubkey(), &fixture.pool, 1_000_000), &[&alice]).unwrap();
+    assert_eq!(fixture.balance(&alice.pubkey(), &fixture.pool.mint_lp), 1_000_000);
+    fixture.set_time(1_300);
+    claim(&mut fixture, &bob, &reward_mint);
+    assert_eq!(fixture.balance(&bob.pubkey(), &reward_mint), 100 * RATE * 3 / 4 + 100 * RATE);
+
+    let farm = decode_farm(&fixture.svm.get_account(&fixture.pool.farm()).unwrap().data).unwrap();
+    assert_eq!(farm.total_staked, 3_000_000);
+    let position = stake_pda(&fixture.pool.farm(), &bob.pubkey()).0;
+    let position = decode_stake_position(&fixture.svm.get_account(&position).unwrap().data).unwrap();
+    assert_eq!((position.amount, position.pending[0]), (3_000_000, 0));
+}
+
+#[test]
+fn set_reward_rate_applies_from_now() {
+    let (mut fixture, reward_mint, alice, _) = farm();
+    fixture.send(instructions::stake(&alice.pubkey(), &fixture.pool, 1_000_000), &[&alice]).unwrap();
+
+    fixture.set_time(1_100);
+    let ix = instructions::set_reward_rate(&fixture.authority.pubkey(), &fixture.pool, 0, 3 * RATE);
+    fixture.send(ix, &[]).unwrap();
+    fixture.set_time(1_200);
+
+    claim(&mut fixture, &alice, &reward_mint);
+    assert_eq!(fixture.balance(&alice.pubkey(), &reward_mint), 100 * RATE + 100 * 3 * RATE);
+}
+
+#[test]
+fn claim_and_unstake_reject_what_is_not_owed() {
+    let (mut fixture, reward_mint, alice, _) = farm();
+    fixture.send(instructions::stake(&alice.pubkey(), &fixture.pool, 1_000_000), &[&alice]).unwrap();
+
+    // no time passed, nothing earned yet
+    let ix = instructions::claim_rewards(&alice.pubkey(), &fixture.pool, 0, &reward_mint);
+    assert_amm_error(fixture.send(ix, &[&alice]), AmmError::ZeroBalance);
+
+    let ix = instructions::unstake(&alice.pubkey(), &fixture.pool, 1_000_001);
+    assert_amm_error(fixture.send(ix, &[&alice]), AmmError::InsufficientBalance);
+}
+
+#[test]
+fn farm_setup_is_authority_only() {
+    let (mut fixture, _, alice, _) = farm();
+
+    let ix = instructions::set_reward_rate(&alice.pubkey(), &fixture.pool, 0, 0);
+    assert_amm_error(fixture.send(ix, &[&alice]), AmmError::InvalidAuthority);
+
+    let other_mint = CreateMint::new(&mut fixture.svm, &fixture.authority).decimals(DECIMALS).send().unwrap();
+    let ix = instructions::add_reward(&alice.pubkey(), &fixture.pool, &other_mint, RATE);
+    assert_amm_error(fixture.send(ix, &[&alice]), AmmError::InvalidAuthority);
+}
+
+#[test]
+fn add_reward_stops_at_max_rewards() {
+    let (mut fixture, _, _, _) = farm();
+    let authority = fixture.authority.pubkey();
+
+    // the farm starts with one reward
+    for _ in 1..MAX_REWARDS {
+        let mint = CreateMint::new(&mut fixture.svm, &fixture.authority).decimals(DECIMALS).send().unwrap();
+        fixture.send(instructions::add_reward(&authority, &fixture.pool, &mint, RATE), &[]).unwrap();
+    }
+    assert_eq!(decode_farm(&fixture.svm.get_account(&fixture.pool.farm()).unwrap().data).unwrap().rewards.len(), MAX_REWARDS);
+
+    let mint = CreateMint::new(&mut fixture.svm, &fixture.authority).decimals(DECIMALS).send().unwrap();
+    let ix = instructions::add_reward(&authority, &fixture.pool, &mint, RATE);
+    assert_amm_error(fixture.send(ix, &[]), AmmError::TooManyRewards);
+}
+
+#[test]
+fn reward_index_past_the_last_reward_is_rejected() {
+    let (mut fixture, reward_mint, alice, _) = farm();
+
+    let ix = instructions::set_reward_rate(&fixture.authority.pubkey(), &fixture.pool, 1, RATE);
+    assert_amm_error(fixture.send(ix, &[]), AmmError::InvalidRewardIndex);
+
+    fixture.send(instructions::stake(&alice.pubkey(), &fixture.pool, 1_000_000), &[&alice]).unwrap();
+    fixture.set_time(1_100);
+    let ix = instructions::claim_rewards(&alice.pubkey(), &fixture.pool, 1, &reward_mint);
+    assert_amm_error(fixture.send(ix, &[&alice]), AmmError::InvalidRewardIndex);
+}
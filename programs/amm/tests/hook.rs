@@ -0,0 +1,111 @@
+mod common;
+
+use amm_client::instructions;
+use amm_test_hook::{HookError, REJECTED_AMOUNT};
+use anchor_lang::error::ERROR_CODE_OFFSET;
+use common::*;
+use litesvm::types::TransactionResult;
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+fn seeded() -> (Fixture, Keypair) {
+    let mut fixture = Fixture::with_hook();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    (fixture, lp)
+}
+
+// the hook logged a call for this stage and action
+fn assert_hook_called(result: &TransactionResult, call: &str) {
+    let logs = &result.as_ref().expect("transaction should have succeeded").logs;
+    let line = format!("Program log: amm_hook {}", call);
+    assert!(logs.iter().any(|log| log.starts_with(&line)), "no `{}` in {:#?}", line, logs);
+}
+
+fn assert_hook_rejected(result: TransactionResult) {
+    let failed = result.expect_err("transaction should have failed");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(ERROR_CODE_OFFSET + HookError::Rejected as u32)),
+        "logs: {:#?}",
+        failed.meta.logs
+    );
+}
+
+#[test]
+fn hook_is_called_before_and_after_each_action() {
+    let (mut fixture, lp) = seeded();
+
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    let result = fixture.send(ix, &[&user]);
+    assert_hook_called(&result, "Before Swap 10000 10000 0");
+    assert_hook_called(&result, "After Swap 10000");
+
+    let depositor = fixture.user(1_000, 4_000);
+    let ix = instructions::deposit(&depositor.pubkey(), &fixture.pool, 2_000, u64::MAX, u64::MAX, None, false, &[]);
+    let result = fixture.send(ix, &[&depositor]);
+    assert_hook_called(&result, "Before Deposit 2000");
+    assert_hook_called(&result, "After Deposit 2000");
+
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 1_000, 0, 0, None, false, true, &[]);
+    let result = fixture.send(ix, &[&lp]);
+    assert_hook_called(&result, "Before Withdraw 1000 0 0");
+    assert_hook_called(&result, "After Withdraw 1000");
+}
+
+#[test]
+fn hook_rejection_reverts_swap() {
+    let (mut fixture, _) = seeded();
+    let user = fixture.user(REJECTED_AMOUNT, 0);
+
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, REJECTED_AMOUNT, true, 0, None, false, &[]);
+    assert_hook_rejected(fixture.send(ix, &[&user]));
+
+    // the tokens had already moved when the after hook failed
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_x), REJECTED_AMOUNT);
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_y), 0);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+    assert_eq!(fixture.config().reserve_x, 1_000_000);
+}
+
+#[test]
+fn hook_rejection_reverts_deposit() {
+    let (mut fixture, _) = seeded();
+    let user = fixture.user(1_000, 4_000);
+
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, REJECTED_AMOUNT, u64::MAX, u64::MAX, None, false, &[]);
+    assert_hook_rejected(fixture.send(ix, &[&user]));
+
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_x), 1_000);
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_y), 4_000);
+    assert_eq!(fixture.mint(&fixture.pool.mint_lp).supply, 2_000_000);
+    assert_eq!(fixture.config().reserve_y, 4_000_000);
+}
+
+#[test]
+fn hook_rejection_reverts_withdraw() {
+    let (mut fixture, lp) = seeded();
+
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, REJECTED_AMOUNT, 0, 0, None, false, true, &[]);
+    assert_hook_rejected(fixture.send(ix, &[&lp]));
+
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_lp), 2_000_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_x), 0);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+    assert_eq!(fixture.config().reserve_x, 1_000_000);
+}
+
+#[test]
+fn hook_is_called_on_claim_fees() {
+    let (mut fixture, lp) = seeded();
+    let trader = fixture.user(100_000, 0);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 100_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+
+    let result = fixture.send(instructions::claim_fees(&lp.pubkey(), &fixture.pool, &[]), &[&lp]);
+    assert_hook_called(&result, "Before ClaimFees");
+    assert_hook_called(&result, "After ClaimFees");
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use amm::{error::AmmError, MINIMUM_LIQUIDITY};
+use amm_client::{ata, instructions};
+use common::*;
+use solana_sdk::signature::Signer;
+
+#[test]
+fn initialize_creates_pool() {
+    let fixture = Fixture::with_pool();
+    let config = fixture.config();
+
+    assert_eq!(config.seed, SEED);
+    assert_eq!(config.authority, Some(fixture.authority.pubkey()));
+    assert_eq!(config.mint_x, fixture.pool.mint_x);
+    assert_eq!(config.mint_y, fixture.pool.mint_y);
+    assert_eq!(config.fee, FEE);
+    assert!(!config.locked);
+    assert!(!config.permissioned);
+    assert_eq!(config.hook_program, None);
+    // no lp decimals given, so they follow the pair
+    assert_eq!(config.lp_decimals, DECIMALS);
+
+    let mint_lp = fixture.mint(&fixture.pool.mint_lp);
+    assert_eq!(mint_lp.decimals, DECIMALS);
+    assert_eq!(mint_lp.supply, 0);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 0);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_y).amount, 0);
+}
+
+#[test]
+fn initialize_with_lp_decimals() {
+    let mut fixture = Fixture::new();
+    let ix = fixture.initialize_ix(None, 0, Some(9));
+    fixture.send(ix, &[]).unwrap();
+
+    assert_eq!(fixture.config().lp_decimals, 9);
+    assert_eq!(fixture.config().authority, None);
+    assert_eq!(fixture.mint(&fixture.pool.mint_lp).decimals, 9);
+}
+
+#[test]
+fn initialize_rejects_lp_decimals_above_max() {
+    let mut fixture = Fixture::new();
+    let ix = fixture.initialize_ix(None, 0, Some(10));
+
+    assert_amm_error(fixture.send(ix, &[]), AmmError::InvalidPrecision);
+}
+
+#[test]
+fn initialize_rejects_negative_emergency_delay() {
+    let mut fixture = Fixture::new();
+    let ix = fixture.initialize_ix(None, -1, None);
+
+    assert_amm_error(fixture.send(ix, &[]), AmmError::InvalidAmount);
+}
+
+#[test]
+fn initialize_twice_fails() {
+    let mut fixture = Fixture::with_pool();
+    let ix = fixture.initialize_ix(None, 0, None);
+
+    assert!(fixture.send(ix, &[]).is_err());
+}
+
+// the initializer holding x and y, and initialize seeding the pool with them
+fn seeded_initialize(x: u64, y: u64) -> (Fixture, solana_sdk::instruction::Instruction) {
+    let mut fixture = Fixture::new();
+    let authority = fixture.authority.pubkey();
+    let (mint_x, mint_y) = (fixture.pool.mint_x, fixture.pool.mint_y);
+    fixture.fund(&authority, &mint_x, x);
+    fixture.fund(&authority, &mint_y, y);
+
+    let ix = instructions::initialize_with_liquidity(&authority, &fixture.pool, FEE, Some(authority), 0, None, false, false, x, y);
+    (fixture, ix)
+}
+
+#[test]
+fn initialize_seeds_initial_liquidity() {
+    let (mut fixture, ix) = seeded_initialize(1_000_000, 4_000_000);
+    fixture.send(ix, &[]).unwrap();
+
+    let authority = fixture.authority.pubkey();
+    let mint_lp = fixture.pool.mint_lp;
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_y).amount, 4_000_000);
+    // sqrt(1_000_000 * 4_000_000), minus what stays locked with the config
+    assert_eq!(fixture.mint(&mint_lp).supply, 2_000_000);
+    assert_eq!(fixture.balance(&authority, &mint_lp), 2_000_000 - MINIMUM_LIQUIDITY);
+    assert_eq!(fixture.balance(&fixture.pool.config, &mint_lp), MINIMUM_LIQUIDITY);
+}
+
+#[test]
+fn initialize_rejects_initial_liquidity_below_minimum() {
+    let (mut fixture, ix) = seeded_initialize(1_000, 1_000);
+
+    assert_amm_error(fixture.send(ix, &[]), AmmError::LiquidityLessThanMinimum);
+}
+
+#[test]
+fn initialize_without_liquidity_rejects_the_seeding_accounts() {
+    let (mut fixture, mut ix) = seeded_initialize(1_000_000, 4_000_000);
+    // initial_x and initial_y are the last two arguments
+    let len = ix.data.len();
+    ix.data[len - 16..].fill(0);
+
+    assert_amm_error(fixture.send(ix, &[]), AmmError::UnusedLiquidityAccounts);
+}
+
+#[test]
+fn seeded_pool_closes_by_burning_the_locked_lp() {
+    let (mut fixture, ix) = seeded_initialize(1_000_000, 4_000_000);
+    fixture.send(ix, &[]).unwrap();
+    let authority = fixture.authority.pubkey();
+
+    let ix = instructions::withdraw(&authority, &fixture.pool, 2_000_000 - MINIMUM_LIQUIDITY, 0, 0, None, false, true, &[]);
+    fixture.send(ix, &[]).unwrap();
+    assert_eq!(fixture.mint(&fixture.pool.mint_lp).supply, MINIMUM_LIQUIDITY);
+
+    // without the locked lp the supply left over counts as someone's
+    assert_amm_error(fixture.send(instructions::close_pool(&authority, &fixture.pool, false), &[]), AmmError::PoolNotEmpty);
+    fixture.send(instructions::close_pool(&authority, &fixture.pool, true), &[]).unwrap();
+
+    assert_eq!(fixture.mint(&fixture.pool.mint_lp).supply, 0);
+    let locked_lp = ata(&fixture.pool.config, &fixture.pool.mint_lp);
+    assert!(fixture.svm.get_account(&locked_lp).is_none_or(|account| account.lamports == 0));
+    assert!(fixture.svm.get_account(&fixture.pool.config).is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn initialize_rejects_a_fee_of_100_percent() {
+    let mut fixture = Fixture::new();
+    let authority = fixture.authority.pubkey();
+    let ix = instructions::initialize(&authority, &fixture.pool, 10_000, None, 0, None, false, false);
+
+    assert_amm_error(fixture.send(ix, &[]), AmmError::InvalidFee);
+}
+
+#[test]
+fn initialize_with_liquidity_needs_the_seeding_accounts() {
+    let mut fixture = Fixture::new();
+    // the builder without liquidity leaves the seeding accounts out, initial_x and initial_y are the last two arguments
+    let mut ix = fixture.initialize_ix(None, 0, None);
+    let len = ix.data.len();
+    ix.data[len - 16..len - 8].copy_from_slice(&1_000_000u64.to_le_bytes());
+    ix.data[len - 8..].copy_from_slice(&4_000_000u64.to_le_bytes());
+
+    assert_amm_error(fixture.send(ix, &[]), AmmError::MissingLiquidityAccounts);
+}
@@ -0,0 +1,316 @@
+mod common;
+
+use amm_client::PoolState;
+use proptest::prelude::*;
+
+// property tests for the pool math, run through amm_client::PoolState which calls the same amm::math functions as the program
+// and checks the instructions make. Random sequences of deposit, swap and withdraw are applied to a model
+// pool and the invariants are checked after every step. A panic anywhere in the math (an unwrap, an
+// overflow, a division by zero) fails the property and proptest shrinks it down to a minimal sequence.
+// The svm module at the bottom replays the same kind of sequences against the real program.
+
+const USERS: usize = 3;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Deposit { user: usize, lp: u64 },
+    Swap { amount: u64, is_x: bool },
+    Withdraw { user: usize, percent: u64 },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..USERS, 1..1_000_000_000u64).prop_map(|(user, lp)| Op::Deposit { user, lp }),
+        (1..1_000_000_000u64, any::<bool>()).prop_map(|(amount, is_x)| Op::Swap { amount, is_x }),
+        (0..USERS, 1..=100u64).prop_map(|(user, percent)| Op::Withdraw { user, percent }),
+    ]
+}
+
+// swaps trade one side for the other, so x and y per lp token only hold still across deposits and withdrawals
+fn liquidity_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..USERS, 1..1_000_000_000u64).prop_map(|(user, lp)| Op::Deposit { user, lp }),
+        (0..USERS, 1..=100u64).prop_map(|(user, percent)| Op::Withdraw { user, percent }),
+    ]
+}
+
+#[derive(Clone, Debug)]
+struct Model {
+    pool: PoolState,
+    holders: [u64; USERS],
+}
+
+impl Model {
+    // the first depositor (user 0) picks the price, like Deposit::deposit on an empty pool
+    fn new(x: u64, y: u64, lp: u64, fee: u16) -> Self {
+        let mut holders = [0; USERS];
+        holders[0] = lp;
+
+        Self {
+            pool: PoolState {
+                reserve_x: x,
+                reserve_y: y,
+                lp_supply: lp,
+                fee,
+                max_swap_in_bps: 0,
+                max_swap_price_move_bps: 0,
+                deposit_cap: 0,
+                user_deposit_cap: 0,
+            },
+            holders,
+        }
+    }
+
+    // applies op the way the instruction would, a failing quote means the transaction reverts
+    fn apply(&mut self, op: &Op) {
+        match *op {
+            Op::Deposit { user, lp } => {
+                let Ok(quote) = self.pool.quote_deposit(lp, u64::MAX, u64::MAX, 0) else {
+                    return;
+                };
+                assert!(quote.x > 0 && quote.y > 0, "{} lp minted for {:?}", lp, quote);
+
+                // amounts that don't fit a token account could never be transferred in
+                let (Some(reserve_x), Some(reserve_y), Some(lp_supply)) = (
+                    self.pool.reserve_x.checked_add(quote.x),
+                    self.pool.reserve_y.checked_add(quote.y),
+                    self.pool.lp_supply.checked_add(lp),
+                ) else {
+                    return;
+                };
+
+                self.pool.reserve_x = reserve_x;
+                self.pool.reserve_y = reserve_y;
+                self.pool.lp_supply = lp_supply;
+                self.holders[user] += lp;
+            }
+            Op::Swap { amount, is_x } => {
+                let Ok(quote) = self.pool.quote_swap(amount, is_x, 0) else {
+                    return;
+                };
+                let k = self.pool.reserve_x as u128 * self.pool.reserve_y as u128;
+
+                let (vault_in, vault_out) = match is_x {
+                    true => (&mut self.pool.reserve_x, &mut self.pool.reserve_y),
+                    false => (&mut self.pool.reserve_y, &mut self.pool.reserve_x),
+                };
+                let Some(new_in) = vault_in.checked_add(quote.amount_in) else {
+                    return;
+                };
+                assert!(quote.amount_out <= *vault_out, "{:?} pays out more than the vault holds", quote);
+                *vault_in = new_in;
+                *vault_out -= quote.amount_out;
+
+                let new_k = self.pool.reserve_x as u128 * self.pool.reserve_y as u128;
+                assert!(new_k >= k, "k went from {} to {} on {:?}", k, new_k, op);
+            }
+            Op::Withdraw { user, percent } => {
+                let amount = self.holders[user] * percent / 100;
+                let Ok(quote) = self.pool.quote_withdraw(amount) else {
+                    return;
+                };
+                assert!(quote.x <= self.pool.reserve_x && quote.y <= self.pool.reserve_y);
+
+                self.pool.reserve_x -= quote.x;
+                self.pool.reserve_y -= quote.y;
+                self.pool.lp_supply -= amount;
+                self.holders[user] -= amount;
+            }
+        }
+    }
+
+    // every holder's lp can be redeemed out of what the vaults hold
+    fn assert_vaults_cover_lp(&self) {
+        assert_eq!(self.holders.iter().sum::<u64>(), self.pool.lp_supply);
+
+        for lp in self.holders.iter().copied().filter(|lp| *lp > 0).chain([self.pool.lp_supply]) {
+            if let Ok(quote) = self.pool.quote_withdraw(lp) {
+                assert!(
+                    quote.x <= self.pool.reserve_x && quote.y <= self.pool.reserve_y,
+                    "{} lp redeems {:?} from {:?}",
+                    lp,
+                    quote,
+                    self.pool
+                );
+            }
+        }
+    }
+
+    // x and y per lp token, compared without dividing: a/b >= c/d <=> a*d >= c*b
+    fn share_value_not_below(&self, before: &PoolState) -> bool {
+        if before.lp_supply == 0 || self.pool.lp_supply == 0 {
+            return true;
+        }
+        self.pool.reserve_x as u128 * before.lp_supply as u128 >= before.reserve_x as u128 * self.pool.lp_supply as u128
+            && self.pool.reserve_y as u128 * before.lp_supply as u128 >= before.reserve_y as u128 * self.pool.lp_supply as u128
+    }
+}
+
+// a * b as (high, low) 128 bit halves, the products below need up to 256 bits
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0, b1, b0) = (a >> 64, a & MASK, b >> 64, b & MASK);
+
+    let (mid, carry) = (a1 * b0).overflowing_add(a0 * b1);
+    let (low, carry_low) = (a0 * b0).overflowing_add(mid << 64);
+    let high = a1 * b1 + (mid >> 64) + ((carry as u128) << 64) + carry_low as u128;
+    (high, low)
+}
+
+// k per lp token squared, reserve_x * reserve_y / lp_supply^2, did not go down:
+// x*y/s^2 >= bx*by/bs^2 <=> x*y*bs^2 >= bx*by*s^2
+fn k_per_share_not_below(pool: &PoolState, before: &PoolState) -> bool {
+    if before.lp_supply == 0 || pool.lp_supply == 0 {
+        return true;
+    }
+    let k = pool.reserve_x as u128 * pool.reserve_y as u128;
+    let k_before = before.reserve_x as u128 * before.reserve_y as u128;
+    mul_wide(k, before.lp_supply as u128 * before.lp_supply as u128)
+        >= mul_wide(k_before, pool.lp_supply as u128 * pool.lp_supply as u128)
+}
+
+fn pool() -> impl Strategy<Value = (u64, u64, u64, u16)> {
+    (1_000..1_000_000_000_000u64, 1_000..1_000_000_000_000u64, 1_000..1_000_000_000_000u64, 0..1_000u16)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn invariants_hold_across_random_sequences(
+        (x, y, lp, fee) in pool(),
+        ops in prop::collection::vec(op(), 1..50),
+    ) {
+        let mut model = Model::new(x, y, lp, fee);
+
+        for op in &ops {
+            model.apply(op);
+            model.assert_vaults_cover_lp();
+        }
+    }
+
+    #[test]
+    fn swaps_never_lower_share_value(
+        (x, y, lp, fee) in pool(),
+        swaps in prop::collection::vec((1..1_000_000_000u64, any::<bool>()), 1..50),
+    ) {
+        let mut model = Model::new(x, y, lp, fee);
+
+        for (amount, is_x) in swaps {
+            let before = model.pool;
+            model.apply(&Op::Swap { amount, is_x });
+
+            // the fee stays in the pool, so every lp token is backed by at least as much k as before
+            prop_assert_eq!(model.pool.lp_supply, before.lp_supply);
+            prop_assert!(k_per_share_not_below(&model.pool, &before), "{:?} -> {:?}", before, model.pool);
+        }
+    }
+
+    #[test]
+    fn liquidity_ops_never_lower_share_value(
+        (x, y, lp, fee) in pool(),
+        ops in prop::collection::vec(liquidity_op(), 1..50),
+    ) {
+        let mut model = Model::new(x, y, lp, fee);
+
+        for op in &ops {
+            let before = model.pool;
+            model.apply(op);
+            prop_assert!(model.share_value_not_below(&before), "{:?} took value from the other lps: {:?} -> {:?}", op, before, model.pool);
+        }
+    }
+}
+
+#[test]
+fn mul_wide_matches_known_products() {
+    assert_eq!(mul_wide(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+    assert_eq!(mul_wide(1 << 64, 1 << 64), (1, 0));
+    assert_eq!(mul_wide(123_456_789, 987_654_321), (0, 121_932_631_112_635_269));
+}
+
+// the same kind of random sequences, sent as transactions to the program in litesvm, see tests/common/mod.rs.
+// Each case boots a fresh svm, so this runs far fewer cases than the model above
+mod svm {
+    use super::*;
+    use amm_client::instructions;
+    use crate::common::*;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    struct Pool {
+        fixture: Fixture,
+        users: Vec<Keypair>,
+    }
+
+    impl Pool {
+        // user 0 makes the first deposit, everyone holds plenty of both tokens
+        fn new(x: u64, y: u64, lp: u64) -> Self {
+            let mut fixture = Fixture::with_pool();
+            let users: Vec<Keypair> = (0..USERS).map(|_| fixture.user(1 << 50, 1 << 50)).collect();
+            let ix = instructions::deposit(&users[0].pubkey(), &fixture.pool, lp, x, y, None, false, &[]);
+            fixture.send(ix, &[&users[0]]).unwrap();
+            Self { fixture, users }
+        }
+
+        fn state(&self) -> PoolState {
+            PoolState::new(&self.fixture.config(), self.fixture.mint(&self.fixture.pool.mint_lp).supply)
+        }
+
+        fn lp_balance(&self, user: usize) -> u64 {
+            self.fixture.balance(&self.users[user].pubkey(), &self.fixture.pool.mint_lp)
+        }
+
+        // sends op, a failing transaction is a revert and leaves the pool as it was
+        fn apply(&mut self, op: &Op) {
+            let pool = self.fixture.pool;
+            let (ix, user) = match *op {
+                Op::Deposit { user, lp } => {
+                    (instructions::deposit(&self.users[user].pubkey(), &pool, lp, u64::MAX, u64::MAX, None, false, &[]), user)
+                }
+                Op::Swap { amount, is_x } => (instructions::swap(&self.users[0].pubkey(), &pool, amount, is_x, 0, None, false, &[]), 0),
+                Op::Withdraw { user, percent } => {
+                    let amount = self.lp_balance(user) * percent / 100;
+                    if amount == 0 {
+                        return;
+                    }
+                    (instructions::withdraw(&self.users[user].pubkey(), &pool, amount, 0, 0, None, false, true, &[]), user)
+                }
+            };
+            let _ = self.fixture.send(ix, &[&self.users[user]]);
+        }
+
+        fn assert_invariants(&self) {
+            let state = self.state();
+            let pool = &self.fixture.pool;
+
+            // nothing is sent to the vaults around the program, so the tracked reserves are exactly the vaults
+            assert_eq!(self.fixture.token_account(&pool.vault_x).amount, state.reserve_x);
+            assert_eq!(self.fixture.token_account(&pool.vault_y).amount, state.reserve_y);
+            assert_eq!((0..USERS).map(|user| self.lp_balance(user)).sum::<u64>(), state.lp_supply);
+
+            if let Ok(quote) = state.quote_withdraw(state.lp_supply) {
+                assert!(quote.x <= state.reserve_x && quote.y <= state.reserve_y, "{:?} from {:?}", quote, state);
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn program_keeps_invariants_across_random_sequences(
+            (x, y, lp) in (1_000..1_000_000_000u64, 1_000..1_000_000_000u64, 1_000..1_000_000_000u64),
+            ops in prop::collection::vec(op(), 1..20),
+        ) {
+            let mut pool = Pool::new(x, y, lp);
+
+            for op in &ops {
+                let before = pool.state();
+                pool.apply(op);
+                pool.assert_invariants();
+                if let Op::Swap { .. } = op {
+                    prop_assert!(k_per_share_not_below(&pool.state(), &before), "{:?}: {:?} -> {:?}", op, before, pool.state());
+                }
+            }
+        }
+    }
+}
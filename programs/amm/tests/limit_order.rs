@@ -0,0 +1,210 @@
+mod common;
+
+use amm::error::AmmError;
+use amm_client::{ata, instructions, limit_order_pda, PoolState};
+use anchor_spl::token::spl_token;
+use common::*;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+fn seeded() -> Fixture {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    fixture
+}
+
+fn state(fixture: &Fixture) -> PoolState {
+    PoolState::new(&fixture.config(), fixture.mint(&fixture.pool.mint_lp).supply)
+}
+
+#[test]
+fn limit_order_fills_once_price_is_reached() {
+    let mut fixture = seeded();
+    let owner = fixture.user(10_000, 0);
+    let keeper = fixture.user(0, 0);
+    let keeper_x = ata(&keeper.pubkey(), &fixture.pool.mint_x);
+
+    // sell 10_000 x (100 of it the bounty) for at least 20% more y than the pool pays right now
+    let now = state(&fixture).quote_swap(9_900, true, 0).unwrap();
+    let min_out = now.amount_out * 6 / 5;
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_000, min_out, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_x), 0);
+
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 1, true, &keeper_x);
+    assert_amm_error(fixture.send(fill.clone(), &[&keeper]), AmmError::LimitNotReached);
+
+    // someone buys a lot of x, pushing its price up past the limit
+    let trader = fixture.user(0, 1_000_000);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 1_000_000, false, 0, None, false, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+
+    let quote = state(&fixture).quote_swap(9_900, true, 0).unwrap();
+    assert!(quote.amount_out >= min_out);
+    fixture.send(fill, &[&keeper]).unwrap();
+
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_y), quote.amount_out);
+    assert_eq!(fixture.token_account(&keeper_x).amount, 100);
+    let order = limit_order_pda(&fixture.pool.config, &owner.pubkey(), 1).0;
+    assert!(fixture.svm.get_account(&order).is_none());
+}
+
+#[test]
+fn cancel_limit_order_returns_the_escrow() {
+    let mut fixture = seeded();
+    let owner = fixture.user(10_000, 0);
+
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 7, true, 10_000, 1_000_000, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+
+    let ix = instructions::cancel_limit_order(&owner.pubkey(), &fixture.pool, 7, true);
+    fixture.send(ix, &[&owner]).unwrap();
+
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_x), 10_000);
+    let order = limit_order_pda(&fixture.pool.config, &owner.pubkey(), 7).0;
+    assert!(fixture.svm.get_account(&order).is_none());
+}
+
+#[test]
+fn place_limit_order_rejects_bounty_above_amount() {
+    let mut fixture = seeded();
+    let owner = fixture.user(10_000, 0);
+
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_000, 1, 10_000);
+    assert_amm_error(fixture.send(ix, &[&owner]), AmmError::InvalidAmount);
+}
+
+#[test]
+fn limit_order_fill_sells_tokens_sent_to_the_escrow() {
+    let mut fixture = seeded();
+    let owner = fixture.user(10_000, 0);
+    let keeper = fixture.user(0, 0);
+    let keeper_x = ata(&keeper.pubkey(), &fixture.pool.mint_x);
+
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_000, 1, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+
+    // a griefer's dust would otherwise keep the escrow from closing
+    let order = limit_order_pda(&fixture.pool.config, &owner.pubkey(), 1).0;
+    let donor = fixture.user(5, 0);
+    let donor_x = ata(&donor.pubkey(), &fixture.pool.mint_x);
+    let ix = spl_token::instruction::transfer(&spl_token::ID, &donor_x, &ata(&order, &fixture.pool.mint_x), &donor.pubkey(), &[], 5).unwrap();
+    fixture.send(ix, &[&donor]).unwrap();
+
+    let quote = state(&fixture).quote_swap(9_905, true, 0).unwrap();
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 1, true, &keeper_x);
+    fixture.send(fill, &[&keeper]).unwrap();
+
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_y), quote.amount_out);
+    assert_eq!(fixture.token_account(&keeper_x).amount, 100);
+    assert!(fixture.svm.get_account(&order).is_none());
+}
+
+#[test]
+fn cancel_limit_order_after_close_pool() {
+    let mut fixture = Fixture::with_pool();
+    let owner = fixture.user(10_000, 0);
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_000, 1_000_000, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+
+    let authority = fixture.authority.pubkey();
+    fixture.send(instructions::close_pool(&authority, &fixture.pool, false), &[]).unwrap();
+
+    let ix = instructions::cancel_limit_order(&owner.pubkey(), &fixture.pool, 1, true);
+    fixture.send(ix, &[&owner]).unwrap();
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_x), 10_000);
+}
+
+#[test]
+fn limit_order_fill_goes_through_the_circuit_breaker() {
+    let mut fixture = seeded();
+    let owner = fixture.user(10_000, 0);
+    let keeper = fixture.user(0, 0);
+    let keeper_x = ata(&keeper.pubkey(), &fixture.pool.mint_x);
+
+    // 9_900 x into 1_000_000 moves y per x by about 2%
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_000, 1, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 1, true, &keeper_x);
+
+    let ix = instructions::set_circuit_breaker(&fixture.authority.pubkey(), &fixture.pool, 100, 0, false);
+    fixture.send(ix, &[]).unwrap();
+    assert_amm_error(fixture.send(fill.clone(), &[&keeper]), AmmError::PriceMoveExceeded);
+
+    // with pause_on_price_move the pool locks and the order stays open
+    let ix = instructions::set_circuit_breaker(&fixture.authority.pubkey(), &fixture.pool, 100, 0, true);
+    fixture.send(ix, &[]).unwrap();
+    fixture.send(fill, &[&keeper]).unwrap();
+
+    assert!(fixture.config().locked);
+    let order = limit_order_pda(&fixture.pool.config, &owner.pubkey(), 1).0;
+    assert!(fixture.svm.get_account(&order).is_some());
+    assert_eq!(fixture.token_account(&ata(&order, &fixture.pool.mint_x)).amount, 10_000);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+}
+
+#[test]
+fn limit_order_fill_rejects_input_over_max_swap_size() {
+    let mut fixture = seeded();
+    let owner = fixture.user(20_101, 0);
+    let keeper = fixture.user(0, 0);
+    let keeper_x = ata(&keeper.pubkey(), &fixture.pool.mint_x);
+
+    // at most 1% of the 1_000_000 x reserve, the bounty doesn't count
+    let ix = instructions::set_caps(&fixture.authority.pubkey(), &fixture.pool, 100, 0, 0);
+    fixture.send(ix, &[]).unwrap();
+
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_101, 1, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 1, true, &keeper_x);
+    assert_amm_error(fixture.send(fill, &[&keeper]), AmmError::SwapTooLarge);
+
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 2, true, 10_000, 1, 0);
+    fixture.send(ix, &[&owner]).unwrap();
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 2, true, &keeper_x);
+    fixture.send(fill, &[&keeper]).unwrap();
+}
+
+#[test]
+fn limit_order_fill_rechecks_the_whitelist() {
+    let mut fixture = seeded();
+    let authority = fixture.authority.pubkey();
+    let owner = fixture.user(10_000, 0);
+    let keeper = fixture.user(0, 0);
+    let keeper_x = ata(&keeper.pubkey(), &fixture.pool.mint_x);
+
+    // placed while the pool was open to anyone
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_000, 1, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+    fixture.send(instructions::set_permissioned(&authority, &fixture.pool, true), &[]).unwrap();
+
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 1, true, &keeper_x);
+    assert_amm_error(fixture.send(fill, &[&keeper]), AmmError::NotWhitelisted);
+
+    // the keeper isn't whitelisted, the fill passes the owner's entry
+    fixture.send(instructions::add_to_whitelist(&authority, &fixture.pool, &owner.pubkey()), &[]).unwrap();
+    fixture.pool.permissioned = true;
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 1, true, &keeper_x);
+    fixture.send(fill, &[&keeper]).unwrap();
+    assert!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_y) > 0);
+}
+
+#[test]
+fn limit_order_fill_rejects_a_pool_that_gained_a_hook() {
+    let mut fixture = seeded();
+    let owner = fixture.user(10_000, 0);
+    let keeper = fixture.user(0, 0);
+    let keeper_x = ata(&keeper.pubkey(), &fixture.pool.mint_x);
+
+    let ix = instructions::place_limit_order(&owner.pubkey(), &fixture.pool, 1, true, 10_000, 1, 100);
+    fixture.send(ix, &[&owner]).unwrap();
+    let ix = instructions::set_hook(&fixture.authority.pubkey(), &fixture.pool, Some(Pubkey::new_unique()));
+    fixture.send(ix, &[]).unwrap();
+
+    let fill = instructions::fill_limit_order(&keeper.pubkey(), &fixture.pool, &owner.pubkey(), 1, true, &keeper_x);
+    assert_amm_error(fixture.send(fill, &[&keeper]), AmmError::HookedPool);
+
+    // the owner can still get the escrow back
+    let ix = instructions::cancel_limit_order(&owner.pubkey(), &fixture.pool, 1, true);
+    fixture.send(ix, &[&owner]).unwrap();
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_x), 10_000);
+}
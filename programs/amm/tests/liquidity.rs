@@ -0,0 +1,381 @@
+mod common;
+
+use amm::{error::AmmError, math};
+use amm_client::{ata, decode_lp_position, instructions, position_pda, PoolState};
+use anchor_lang::error::ErrorCode;
+use anchor_spl::token::spl_token;
+use common::*;
+use solana_sdk::{instruction::AccountMeta, signature::Signer};
+
+fn state(fixture: &Fixture) -> PoolState {
+    PoolState::new(&fixture.config(), fixture.mint(&fixture.pool.mint_lp).supply)
+}
+
+#[test]
+fn first_deposit_sets_the_price() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_y).amount, 4_000_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_lp), 2_000_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_x), 0);
+}
+
+#[test]
+fn deposit_is_proportional() {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    let quote = state(&fixture).quote_deposit(500_000, u64::MAX, u64::MAX, 0).unwrap();
+    let user = fixture.user(quote.x, quote.y);
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 500_000, quote.x, quote.y, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_lp), 500_000);
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_x), 0);
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_y), 0);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000 + quote.x);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_y).amount, 4_000_000 + quote.y);
+}
+
+#[test]
+fn deposit_rejects_zero_amount() {
+    let mut fixture = Fixture::with_pool();
+    let user = fixture.user(1_000, 1_000);
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 0, 1_000, 1_000, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidAmount);
+}
+
+#[test]
+fn deposit_rejects_slippage() {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    let quote = state(&fixture).quote_deposit(500_000, u64::MAX, u64::MAX, 0).unwrap();
+    let user = fixture.user(quote.x, quote.y);
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 500_000, quote.x - 1, quote.y, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::SlippageExceeded);
+}
+
+#[test]
+fn deposit_rejects_expired() {
+    let mut fixture = Fixture::with_pool();
+    fixture.set_time(1_000);
+    let user = fixture.user(1_000, 1_000);
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 1_000, 1_000, 1_000, Some(999), false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::OfferExpired);
+}
+
+#[test]
+fn deposit_rejects_locked_pool() {
+    let mut fixture = Fixture::with_pool();
+    let lock = instructions::lock(&fixture.authority.pubkey(), &fixture.pool);
+    fixture.send(lock, &[]).unwrap();
+
+    let user = fixture.user(1_000, 1_000);
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 1_000, 1_000, 1_000, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::PoolLocked);
+}
+
+#[test]
+fn deposit_to_recipient_still_sets_up_the_payers_position() {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let user = fixture.user(1_000, 4_000);
+    let recipient = fixture.wallet();
+    let mint_lp = fixture.pool.mint_lp;
+    fixture.fund(&recipient.pubkey(), &mint_lp, 0);
+    let recipient_lp = ata(&recipient.pubkey(), &mint_lp);
+
+    let ix = instructions::deposit_to(&user.pubkey(), &fixture.pool, 2_000, 1_000, 4_000, None, false, &recipient_lp, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+    assert_eq!(fixture.token_account(&recipient_lp).amount, 2_000);
+
+    let position = position_pda(&fixture.pool.config, &user.pubkey());
+    let data = decode_lp_position(&fixture.svm.get_account(&position.0).unwrap().data).unwrap();
+    assert_eq!((data.config, data.user, data.bump), (fixture.pool.config, user.pubkey(), position.1));
+    assert_eq!(data.lp_amount, 0);
+
+    // user_lp was left out of the deposit, so the payer's lp account doesn't exist yet
+    assert!(fixture.svm.get_account(&ata(&user.pubkey(), &mint_lp)).is_none());
+
+    // lp handed back to the payer can be withdrawn through that position
+    fixture.fund(&user.pubkey(), &mint_lp, 0);
+    let user_lp = ata(&user.pubkey(), &mint_lp);
+    let ix = spl_token::instruction::transfer(&spl_token::ID, &recipient_lp, &user_lp, &recipient.pubkey(), &[], 2_000).unwrap();
+    fixture.send(ix, &[&recipient]).unwrap();
+    let ix = instructions::withdraw(&user.pubkey(), &fixture.pool, 2_000, 0, 0, None, false, true, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+    assert_eq!(fixture.balance(&user.pubkey(), &mint_lp), 0);
+}
+
+#[test]
+fn deposit_needs_exactly_one_lp_destination() {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let user = fixture.user(2_000, 8_000);
+    let recipient = fixture.wallet();
+    let mint_lp = fixture.pool.mint_lp;
+    fixture.fund(&recipient.pubkey(), &mint_lp, 0);
+    let recipient_lp = ata(&recipient.pubkey(), &mint_lp);
+    let user_lp = ata(&user.pubkey(), &mint_lp);
+
+    // the builders never pass both or neither, so swap user_lp in and out of the account list by hand.
+    // With user_x and user_y passed, the first account left out is user_lp
+    let mut both = instructions::deposit_to(&user.pubkey(), &fixture.pool, 2_000, 1_000, 4_000, None, false, &recipient_lp, &[]);
+    let slot = both.accounts.iter().position(|meta| meta.pubkey == amm::ID).unwrap();
+    both.accounts[slot] = AccountMeta::new(user_lp, false);
+    assert_amm_error(fixture.send(both, &[&user]), AmmError::InvalidLpDestination);
+
+    let mut neither = instructions::deposit(&user.pubkey(), &fixture.pool, 2_000, 1_000, 4_000, None, false, &[]);
+    let slot = neither.accounts.iter().position(|meta| meta.pubkey == user_lp).unwrap();
+    neither.accounts[slot] = AccountMeta::new_readonly(amm::ID, false);
+    assert_amm_error(fixture.send(neither, &[&user]), AmmError::InvalidLpDestination);
+}
+
+#[test]
+fn deposit_rejects_source_of_the_wrong_mint() {
+    let mut fixture = Fixture::with_pool();
+    let user = fixture.user(1_000, 4_000);
+    let user_y = ata(&user.pubkey(), &fixture.pool.mint_y);
+
+    // paying x out of a y account
+    let ix = instructions::deposit_from(&user.pubkey(), &fixture.pool, 2_000, 1_000, 4_000, None, Some(user_y), None, None, &[]);
+    assert_anchor_error(fixture.send(ix, &[&user]), ErrorCode::ConstraintTokenMint);
+}
+
+#[test]
+fn withdraw_returns_tokens() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    let quote = state(&fixture).quote_withdraw(500_000).unwrap();
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 500_000, quote.x, quote.y, None, false, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_lp), 1_500_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_x), quote.x);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_y), quote.y);
+    assert_eq!(fixture.mint(&fixture.pool.mint_lp).supply, 1_500_000);
+}
+
+#[test]
+fn withdraw_rejects_slippage() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    let quote = state(&fixture).quote_withdraw(500_000).unwrap();
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 500_000, quote.x, quote.y + 1, None, false, true, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::SlippageExceeded);
+}
+
+#[test]
+fn withdraw_rejects_more_than_balance() {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    let quote = state(&fixture).quote_deposit(100_000, u64::MAX, u64::MAX, 0).unwrap();
+    let user = fixture.user(quote.x, quote.y);
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 100_000, quote.x, quote.y, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+
+    let ix = instructions::withdraw(&user.pubkey(), &fixture.pool, 200_000, 0, 0, None, false, true, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InsufficientBalance);
+}
+
+#[test]
+fn withdraw_rejects_zero_amount() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 0, 0, 0, None, false, true, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::InvalidAmount);
+}
+
+#[test]
+fn withdraw_rejects_expired() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    fixture.set_time(1_000);
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 1_000, 0, 0, Some(999), false, true, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::OfferExpired);
+}
+
+#[test]
+fn withdraw_rejects_locked_pool() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let lock = instructions::lock(&fixture.authority.pubkey(), &fixture.pool);
+    fixture.send(lock, &[]).unwrap();
+
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 1_000, 0, 0, None, false, true, &[]);
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::PoolLocked);
+}
+
+#[test]
+fn emergency_withdraw_opens_after_delay() {
+    let mut fixture = Fixture::new();
+    let ix = fixture.initialize_ix(Some(fixture.authority.pubkey()), 3_600, None);
+    fixture.send(ix, &[]).unwrap();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    fixture.set_time(10_000);
+    let lock = instructions::lock(&fixture.authority.pubkey(), &fixture.pool);
+    fixture.send(lock, &[]).unwrap();
+
+    let ix = instructions::emergency_withdraw(&lp.pubkey(), &fixture.pool, 1_000_000, 0, 0, true);
+    assert_amm_error(fixture.send(ix.clone(), &[&lp]), AmmError::EmergencyWithdrawUnavailable);
+
+    fixture.set_time(10_000 + 3_600);
+    fixture.send(ix, &[&lp]).unwrap();
+
+    // half the lp supply gets exactly half of each vault
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_x), 500_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_y), 2_000_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_lp), 1_000_000);
+}
+
+#[test]
+fn emergency_withdraw_rejects_unlocked_pool() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    let ix = instructions::emergency_withdraw(&lp.pubkey(), &fixture.pool, 1_000, 0, 0, true);
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::EmergencyWithdrawUnavailable);
+}
+
+#[test]
+fn deposit_rejects_going_over_the_deposit_cap() {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let ix = instructions::set_caps(&fixture.authority.pubkey(), &fixture.pool, 0, 2_500_000, 0);
+    fixture.send(ix, &[]).unwrap();
+
+    let user = fixture.user(1_000_000, 4_000_000);
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 500_001, u64::MAX, u64::MAX, None, false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::DepositCapExceeded);
+    assert!(matches!(state(&fixture).quote_deposit(500_001, u64::MAX, u64::MAX, 0), Err(AmmError::DepositCapExceeded)));
+    assert!(state(&fixture).quote_deposit(500_000, u64::MAX, u64::MAX, 0).is_ok());
+
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 500_000, u64::MAX, u64::MAX, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+}
+
+#[test]
+fn withdrawing_frees_up_the_user_deposit_cap() {
+    let mut fixture = Fixture::with_pool();
+    let ix = instructions::set_caps(&fixture.authority.pubkey(), &fixture.pool, 0, 0, 1_000_000);
+    fixture.send(ix, &[]).unwrap();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 1_000_000);
+
+    let deposit = instructions::deposit(&lp.pubkey(), &fixture.pool, 500_000, u64::MAX, u64::MAX, None, false, &[]);
+    assert_amm_error(fixture.send(deposit.clone(), &[&lp]), AmmError::UserDepositCapExceeded);
+    let position = position_pda(&fixture.pool.config, &lp.pubkey()).0;
+    let deposited = decode_lp_position(&fixture.svm.get_account(&position).unwrap().data).unwrap().deposited;
+    assert_eq!(deposited, 1_000_000);
+    assert!(matches!(state(&fixture).quote_deposit(500_000, u64::MAX, u64::MAX, deposited), Err(AmmError::UserDepositCapExceeded)));
+    // another user starts from 0 under the same cap
+    assert!(state(&fixture).quote_deposit(500_000, u64::MAX, u64::MAX, 0).is_ok());
+
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 500_000, 0, 0, None, false, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+    fixture.send(deposit, &[&lp]).unwrap();
+
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_lp), 1_000_000);
+}
+
+#[test]
+fn claim_fees_pays_out_swap_fees() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let trader = fixture.user(100_000, 0);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 100_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+
+    let config = fixture.config();
+    let position = position_pda(&fixture.pool.config, &lp.pubkey()).0;
+    let position = decode_lp_position(&fixture.svm.get_account(&position).unwrap().data).unwrap();
+    let (fees_x, fees_y) = position.earned(&config, 2_000_000).unwrap();
+    // the only lp earns the whole 0.3% fee on the x sold
+    assert!(fees_x > 0 && fees_x <= 300);
+    assert_eq!(fees_y, 0);
+    let supply = fixture.mint(&fixture.pool.mint_lp).supply;
+
+    fixture.send(instructions::claim_fees(&lp.pubkey(), &fixture.pool, &[]), &[&lp]).unwrap();
+
+    // the burned lp is paid its share of both reserves, which covers the x fees, the y side isn't left in the pool
+    let burned = 2_000_000 - fixture.balance(&lp.pubkey(), &fixture.pool.mint_lp);
+    let paid = math::withdraw_amounts(config.reserve_x, config.reserve_y, supply, burned).unwrap();
+    assert!(burned > 0);
+    assert!(paid.x >= fees_x && paid.y > 0);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_x), paid.x);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_y), paid.y);
+    assert_eq!(fixture.config().reserve_x, config.reserve_x - paid.x);
+    assert_eq!(fixture.config().reserve_y, config.reserve_y - paid.y);
+
+    // everything earned so far was paid
+    let ix = instructions::claim_fees(&lp.pubkey(), &fixture.pool, &[]);
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::ZeroBalance);
+}
+
+#[test]
+fn claim_fees_on_permissioned_pool_requires_whitelist() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let trader = fixture.user(100_000, 0);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 100_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+    let ix = instructions::set_permissioned(&fixture.authority.pubkey(), &fixture.pool, true);
+    fixture.send(ix, &[]).unwrap();
+
+    let ix = instructions::claim_fees(&lp.pubkey(), &fixture.pool, &[]);
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::NotWhitelisted);
+}
+
+#[test]
+fn deposit_rejects_lp_whose_amounts_overflow() {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let user = fixture.user(0, 0);
+
+    // 2 y per lp, u64::MAX lp would take twice u64::MAX y
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, u64::MAX, u64::MAX, u64::MAX, None, false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::Overflow);
+}
+
+#[test]
+fn deposit_rejects_a_side_without_a_token_account() {
+    let mut fixture = Fixture::with_pool();
+    let user = fixture.user(1_000, 4_000);
+    let user_x = ata(&user.pubkey(), &fixture.pool.mint_x);
+
+    // no source_x and no wrap_sol, so user_x has to pay
+    let mut ix = instructions::deposit(&user.pubkey(), &fixture.pool, 2_000, 1_000, 4_000, None, false, &[]);
+    let slot = ix.accounts.iter().position(|meta| meta.pubkey == user_x).unwrap();
+    ix.accounts[slot] = AccountMeta::new_readonly(amm::ID, false);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::MissingTokenAccount);
+}
+
+#[test]
+fn claim_fees_rejects_a_pool_without_lp() {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let trader = fixture.user(100_000, 0);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 100_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+
+    // the withdraw settles the fees into fees_owed and pays them out with the reserves, burning the whole supply
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 2_000_000, 0, 0, None, false, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+    assert_eq!(fixture.mint(&fixture.pool.mint_lp).supply, 0);
+
+    let ix = instructions::claim_fees(&lp.pubkey(), &fixture.pool, &[]);
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::NoLiquidityInPool);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use amm::error::AmmError;
+use amm_client::{ata, instructions, PoolState};
+use common::*;
+use litesvm_token::MintTo;
+use solana_sdk::signature::Signer;
+
+fn seeded() -> Fixture {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    fixture
+}
+
+fn state(fixture: &Fixture) -> PoolState {
+    PoolState::new(&fixture.config(), fixture.mint(&fixture.pool.mint_lp).supply)
+}
+
+// sends x and y straight to the vaults, around the program
+fn donate(fixture: &mut Fixture, x: u64, y: u64) {
+    let pool = fixture.pool;
+    for (mint, vault, amount) in [(pool.mint_x, pool.vault_x, x), (pool.mint_y, pool.vault_y, y)] {
+        MintTo::new(&mut fixture.svm, &fixture.authority, &mint, &vault, amount).send().unwrap();
+    }
+}
+
+#[test]
+fn donations_do_not_move_the_price() {
+    let mut fixture = seeded();
+    let quote = state(&fixture).quote_swap(10_000, true, 0).unwrap();
+    donate(&mut fixture, 0, 4_000_000);
+
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_y), quote.amount_out);
+    assert_eq!(fixture.config().reserve_y, 4_000_000 - quote.amount_out);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_y).amount, 8_000_000 - quote.amount_out);
+}
+
+#[test]
+fn skim_pays_out_the_excess() {
+    let mut fixture = seeded();
+    donate(&mut fixture, 1_000, 2_000);
+
+    let recipient = fixture.user(0, 0);
+    let (recipient_x, recipient_y) = (ata(&recipient.pubkey(), &fixture.pool.mint_x), ata(&recipient.pubkey(), &fixture.pool.mint_y));
+    let ix = instructions::skim(&fixture.authority.pubkey(), &fixture.pool, &recipient_x, &recipient_y);
+    fixture.send(ix, &[]).unwrap();
+
+    assert_eq!(fixture.token_account(&recipient_x).amount, 1_000);
+    assert_eq!(fixture.token_account(&recipient_y).amount, 2_000);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+    assert_eq!(fixture.config().reserve_x, 1_000_000);
+
+    // nothing left to skim
+    let ix = instructions::skim(&fixture.authority.pubkey(), &fixture.pool, &recipient_x, &recipient_y);
+    assert_amm_error(fixture.send(ix, &[]), AmmError::ZeroBalance);
+}
+
+#[test]
+fn sync_adds_the_excess_to_the_reserves() {
+    let mut fixture = seeded();
+    donate(&mut fixture, 1_000_000, 4_000_000);
+
+    fixture.send(instructions::sync(&fixture.authority.pubkey(), &fixture.pool), &[]).unwrap();
+    assert_eq!(fixture.config().reserve_x, 2_000_000);
+    assert_eq!(fixture.config().reserve_y, 8_000_000);
+
+    // the same lp is now backed by twice the tokens
+    let quote = state(&fixture).quote_withdraw(1_000_000).unwrap();
+    assert_eq!((quote.x, quote.y), (1_000_000, 4_000_000));
+}
+
+#[test]
+fn skim_and_sync_are_authority_only() {
+    let mut fixture = seeded();
+    donate(&mut fixture, 1_000, 0);
+    let user = fixture.user(0, 0);
+
+    let ix = instructions::sync(&user.pubkey(), &fixture.pool);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidAuthority);
+
+    let ix = instructions::skim(&user.pubkey(), &fixture.pool, &ata(&user.pubkey(), &fixture.pool.mint_x), &ata(&user.pubkey(), &fixture.pool.mint_y));
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidAuthority);
+}
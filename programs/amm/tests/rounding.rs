@@ -0,0 +1,148 @@
+use amm::{
+    error::AmmError,
+    math::{self, Rounding},
+};
+use proptest::prelude::*;
+
+// every amount amm::math computes rounds in the pool's favour. These check the consequence that matters:
+// splitting an operation into many tiny ones never gets a user more than doing it in one go,
+// so dust can't be farmed out of the pool by repetition.
+
+fn reserves() -> impl Strategy<Value = (u64, u64, u64)> {
+    (1_000..1_000_000_000_000u64, 1_000..1_000_000_000_000u64, 1_000..1_000_000_000_000u64)
+}
+
+#[test]
+fn directions_favour_the_pool() {
+    // 7 * 1 / 2 = 3.5 either way
+    assert_eq!(math::mul_div(7, 1, 2, Rounding::Down).unwrap(), 3);
+    assert_eq!(math::mul_div(7, 1, 2, Rounding::Up).unwrap(), 4);
+
+    // 1 lp out of 3 against 10 tokens: deposits pay 4, withdrawals get 3
+    assert_eq!(math::deposit_amounts(10, 10, 3, 1).unwrap().x, 4);
+    assert_eq!(math::withdraw_amounts(10, 10, 3, 1).unwrap().x, 3);
+
+    // 0.3% of 1 token is still a fee of 1
+    assert_eq!(math::swap_out(1_000, 1_000, 1, 30).unwrap().fee, 1);
+    assert_eq!(math::split_fee(1, 30, Rounding::Up).unwrap().fee, 1);
+
+    // sqrt(2 * 5) = 3.16, seeding mints 3
+    assert_eq!(math::initial_lp(2, 5).unwrap(), 3);
+    assert_eq!(math::initial_lp(u64::MAX, u64::MAX).unwrap(), u64::MAX);
+}
+
+// inputs no instruction can pass are still refused by the math itself: place_twap_order checks the slippage before
+// min_out_at_price sees it, and initialize keeps fees under 100%
+#[test]
+fn bad_inputs_are_errors() {
+    assert!(matches!(math::split_fee(1_000, 10_001, Rounding::Up), Err(AmmError::InvalidFee)));
+    assert!(matches!(math::swap_out(1_000, 1_000, 10, 10_001), Err(AmmError::InvalidFee)));
+    assert!(matches!(math::swap_in(1_000, 1_000, 10, 10_000), Err(AmmError::InvalidFee)));
+    assert!(matches!(math::min_out_at_price(1_000, 1, 10_001), Err(AmmError::InvalidFee)));
+
+    assert!(matches!(math::swap_out(0, 1_000, 10, 30), Err(AmmError::NoLiquidityInPool)));
+    assert!(matches!(math::deposit_amounts(1_000, 1_000, 0, 10), Err(AmmError::NoLiquidityInPool)));
+    assert!(matches!(math::withdraw_amounts(1_000, 1_000, 0, 10), Err(AmmError::NoLiquidityInPool)));
+    assert!(matches!(math::spot_price(0, 1_000), Err(AmmError::NoLiquidityInPool)));
+
+    assert!(matches!(math::mul_div(u64::MAX, 2, 1, Rounding::Down), Err(AmmError::Overflow)));
+    assert!(matches!(math::deposit_amounts(2, 1, 1, u64::MAX), Err(AmmError::Overflow)));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn tiny_deposits_cost_at_least_one_large_deposit(
+        (x, y, supply) in reserves(),
+        tiny in 1..1_000u64,
+        count in 1..200u64,
+    ) {
+        let large = math::deposit_amounts(x, y, supply, tiny * count).unwrap();
+
+        let (mut vx, mut vy, mut s) = (x, y, supply);
+        let (mut paid_x, mut paid_y) = (0u64, 0u64);
+        for _ in 0..count {
+            let amounts = math::deposit_amounts(vx, vy, s, tiny).unwrap();
+            vx += amounts.x;
+            vy += amounts.y;
+            s += tiny;
+            paid_x += amounts.x;
+            paid_y += amounts.y;
+        }
+
+        prop_assert!(paid_x >= large.x && paid_y >= large.y);
+    }
+
+    #[test]
+    fn tiny_withdrawals_pay_at_most_one_large_withdrawal(
+        (x, y, supply) in reserves(),
+        tiny in 1..1_000u64,
+        count in 1..200u64,
+    ) {
+        prop_assume!(tiny * count <= supply);
+        let large = math::withdraw_amounts(x, y, supply, tiny * count).unwrap();
+
+        let (mut vx, mut vy, mut s) = (x, y, supply);
+        let (mut got_x, mut got_y) = (0u64, 0u64);
+        for _ in 0..count {
+            let amounts = math::withdraw_amounts(vx, vy, s, tiny).unwrap();
+            vx -= amounts.x;
+            vy -= amounts.y;
+            s -= tiny;
+            got_x += amounts.x;
+            got_y += amounts.y;
+        }
+
+        prop_assert!(got_x <= large.x && got_y <= large.y);
+    }
+
+    #[test]
+    fn tiny_swaps_pay_at_most_one_large_swap(
+        (x, y, _) in reserves(),
+        tiny in 1..10_000u64,
+        count in 1..200u64,
+        fee in 0..1_000u16,
+    ) {
+        let large = math::swap_out(x, y, tiny * count, fee).unwrap();
+
+        let (mut reserve_in, mut reserve_out) = (x, y);
+        let mut got = 0u64;
+        for _ in 0..count {
+            let res = math::swap_out(reserve_in, reserve_out, tiny, fee).unwrap();
+            reserve_in += res.amount_in;
+            reserve_out -= res.amount_out;
+            got += res.amount_out;
+        }
+
+        prop_assert!(got <= large.amount_out);
+    }
+
+    #[test]
+    fn deposit_withdraw_round_trips_never_profit(
+        (x, y, supply) in reserves(),
+        lp in 1..1_000_000u64,
+        count in 1..100u64,
+    ) {
+        let (mut vx, mut vy) = (x, y);
+        for _ in 0..count {
+            let paid = math::deposit_amounts(vx, vy, supply, lp).unwrap();
+            let got = math::withdraw_amounts(vx + paid.x, vy + paid.y, supply + lp, lp).unwrap();
+            prop_assert!(got.x <= paid.x && got.y <= paid.y);
+            vx = vx + paid.x - got.x;
+            vy = vy + paid.y - got.y;
+        }
+    }
+
+    #[test]
+    fn exact_out_swaps_deliver_at_least_the_amount_asked(
+        (x, y, _) in reserves(),
+        amount_out in 1..1_000_000u64,
+        fee in 0..1_000u16,
+    ) {
+        prop_assume!(amount_out < y);
+        let quote = math::swap_in(x, y, amount_out, fee).unwrap();
+        let res = math::swap_out(x, y, quote.amount_in, fee).unwrap();
+        prop_assert!(res.amount_out >= amount_out);
+    }
+}
@@ -0,0 +1,298 @@
+mod common;
+
+use amm::error::AmmError;
+use amm_client::{ata, instructions, PoolState};
+use anchor_lang::error::ErrorCode;
+use anchor_spl::token::spl_token;
+use common::*;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+fn seeded() -> Fixture {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    fixture
+}
+
+fn state(fixture: &Fixture) -> PoolState {
+    PoolState::new(&fixture.config(), fixture.mint(&fixture.pool.mint_lp).supply)
+}
+
+#[test]
+fn swap_x_for_y_matches_quote() {
+    let mut fixture = seeded();
+    let quote = state(&fixture).quote_swap(10_000, true, 0).unwrap();
+
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, quote.amount_out, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_x), 10_000 - quote.amount_in);
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_y), quote.amount_out);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000 + quote.amount_in);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_y).amount, 4_000_000 - quote.amount_out);
+}
+
+#[test]
+fn swap_y_for_x_matches_quote() {
+    let mut fixture = seeded();
+    let quote = state(&fixture).quote_swap(40_000, false, 0).unwrap();
+
+    let user = fixture.user(0, 40_000);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 40_000, false, quote.amount_out, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_x), quote.amount_out);
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_y), 40_000 - quote.amount_in);
+}
+
+#[test]
+fn swap_rejects_slippage() {
+    let mut fixture = seeded();
+    let quote = state(&fixture).quote_swap(10_000, true, 0).unwrap();
+
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, quote.amount_out + 1, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::SlippageExceeded);
+}
+
+#[test]
+fn swap_rejects_zero_amount() {
+    let mut fixture = seeded();
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 0, true, 0, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidAmount);
+}
+
+#[test]
+fn swap_rejects_expired() {
+    let mut fixture = seeded();
+    fixture.set_time(1_000);
+    let user = fixture.user(10_000, 0);
+
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, Some(999), false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::OfferExpired);
+
+    // the deadline itself is still fine
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, Some(1_000), false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+}
+
+#[test]
+fn swap_rejects_locked_pool() {
+    let mut fixture = seeded();
+    let lock = instructions::lock(&fixture.authority.pubkey(), &fixture.pool);
+    fixture.send(lock, &[]).unwrap();
+
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::PoolLocked);
+}
+
+#[test]
+fn swap_on_permissioned_pool_requires_whitelist() {
+    let mut fixture = seeded();
+    let authority = fixture.authority.pubkey();
+    let ix = instructions::set_permissioned(&authority, &fixture.pool, true);
+    fixture.send(ix, &[]).unwrap();
+
+    let user = fixture.user(20_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::NotWhitelisted);
+
+    let ix = instructions::add_to_whitelist(&authority, &fixture.pool, &user.pubkey());
+    fixture.send(ix, &[]).unwrap();
+    fixture.pool.permissioned = true;
+
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+}
+
+#[test]
+fn swap_rejects_missing_hook_program() {
+    let mut fixture = seeded();
+    let ix = instructions::set_hook(&fixture.authority.pubkey(), &fixture.pool, Some(Pubkey::new_unique()));
+    fixture.send(ix, &[]).unwrap();
+
+    // fixture.pool still thinks there is no hook, so the hook account is left out
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidHookProgram);
+}
+
+#[test]
+fn swap_rejects_wrap_sol_without_native_mint() {
+    let mut fixture = seeded();
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, true, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::NoNativeMint);
+}
+
+#[test]
+fn swap_creates_the_buyers_token_account() {
+    let mut fixture = seeded();
+    let quote = state(&fixture).quote_swap(10_000, true, 0).unwrap();
+
+    // first time buyer of y, only holds x
+    let user = fixture.wallet();
+    let mint_x = fixture.pool.mint_x;
+    fixture.fund(&user.pubkey(), &mint_x, 10_000);
+
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, quote.amount_out, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_y), quote.amount_out);
+}
+
+#[test]
+fn swap_pays_out_to_recipient() {
+    let mut fixture = seeded();
+    let quote = state(&fixture).quote_swap(10_000, true, 0).unwrap();
+
+    // only holds x
+    let user = fixture.wallet();
+    let mint_x = fixture.pool.mint_x;
+    fixture.fund(&user.pubkey(), &mint_x, 10_000);
+    let recipient = fixture.user(0, 0);
+    let recipient_y = ata(&recipient.pubkey(), &fixture.pool.mint_y);
+
+    let ix = instructions::swap_to(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &recipient_y, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+
+    assert_eq!(fixture.token_account(&recipient_y).amount, quote.amount_out);
+    // the recipient replaces the user's y account, so none gets created for them
+    assert!(fixture.svm.get_account(&ata(&user.pubkey(), &fixture.pool.mint_y)).is_none());
+}
+
+#[test]
+fn swap_rejects_recipient_of_the_wrong_mint() {
+    let mut fixture = seeded();
+    let user = fixture.user(10_000, 0);
+    let recipient = fixture.user(0, 0);
+    let recipient_x = ata(&recipient.pubkey(), &fixture.pool.mint_x);
+
+    let ix = instructions::swap_to(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &recipient_x, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidToken);
+}
+
+#[test]
+fn swap_rejects_source_of_the_wrong_mint() {
+    let mut fixture = seeded();
+    let user = fixture.user(10_000, 10_000);
+    let user_y = ata(&user.pubkey(), &fixture.pool.mint_y);
+
+    // selling x out of a y account
+    let ix = instructions::swap_from(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, &user_y, None, &[]);
+    assert_anchor_error(fixture.send(ix, &[&user]), ErrorCode::ConstraintTokenMint);
+}
+
+#[test]
+fn swap_by_approved_delegate() {
+    let mut fixture = seeded();
+    let quote = state(&fixture).quote_swap(10_000, true, 0).unwrap();
+
+    let owner = fixture.user(10_000, 0);
+    let delegate = fixture.user(0, 0);
+    let source = ata(&owner.pubkey(), &fixture.pool.mint_x);
+    let owner_y = ata(&owner.pubkey(), &fixture.pool.mint_y);
+
+    let ix = instructions::swap_from(&delegate.pubkey(), &fixture.pool, 10_000, true, 0, None, &source, Some(owner_y), &[]);
+    assert_amm_error(fixture.send(ix.clone(), &[&delegate]), AmmError::NotDelegated);
+
+    let approve = spl_token::instruction::approve(&spl_token::ID, &source, &delegate.pubkey(), &owner.pubkey(), &[], 10_000).unwrap();
+    fixture.send(approve, &[&owner]).unwrap();
+    fixture.send(ix, &[&delegate]).unwrap();
+
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_x), 0);
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_y), quote.amount_out);
+}
+
+#[test]
+fn swap_rejects_price_move_past_circuit_breaker() {
+    let mut fixture = seeded();
+    // 10_000 x into 1_000_000 moves y per x by about 2%
+    let ix = instructions::set_circuit_breaker(&fixture.authority.pubkey(), &fixture.pool, 100, 0, false);
+    fixture.send(ix, &[]).unwrap();
+
+    let user = fixture.user(20_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::PriceMoveExceeded);
+    assert!(matches!(state(&fixture).quote_swap(10_000, true, 0), Err(AmmError::PriceMoveExceeded)));
+
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 2_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+}
+
+#[test]
+fn swaps_in_one_slot_add_up_against_slot_limit() {
+    let mut fixture = seeded();
+    let ix = instructions::set_circuit_breaker(&fixture.authority.pubkey(), &fixture.pool, 0, 100, false);
+    fixture.send(ix, &[]).unwrap();
+
+    // each swap moves the price about 0.8%, the second one takes the slot past 1%
+    let user = fixture.user(20_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 4_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 4_001, true, 0, None, false, &[]);
+    assert_amm_error(fixture.send(ix.clone(), &[&user]), AmmError::PriceMoveExceeded);
+
+    // a new slot starts measuring from the current price
+    let slot = fixture.svm.get_sysvar::<solana_sdk::clock::Clock>().slot;
+    fixture.svm.warp_to_slot(slot + 1);
+    fixture.send(ix, &[&user]).unwrap();
+}
+
+#[test]
+fn circuit_breaker_can_pause_the_pool() {
+    let mut fixture = seeded();
+    let ix = instructions::set_circuit_breaker(&fixture.authority.pubkey(), &fixture.pool, 100, 0, true);
+    fixture.send(ix, &[]).unwrap();
+
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    fixture.send(ix.clone(), &[&user]).unwrap();
+
+    // the pool locked instead of trading
+    assert!(fixture.config().locked);
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_x), 10_000);
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::PoolLocked);
+}
+
+#[test]
+fn circuit_breaker_is_authority_only() {
+    let mut fixture = seeded();
+    let user = fixture.user(0, 0);
+    let ix = instructions::set_circuit_breaker(&user.pubkey(), &fixture.pool, 100, 100, true);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidAuthority);
+}
+
+#[test]
+fn swap_rejects_input_over_max_swap_size() {
+    let mut fixture = seeded();
+    // at most 1% of the 1_000_000 x reserve
+    let ix = instructions::set_caps(&fixture.authority.pubkey(), &fixture.pool, 100, 0, 0);
+    fixture.send(ix, &[]).unwrap();
+
+    let user = fixture.user(20_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_001, true, 0, None, false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::SwapTooLarge);
+    assert!(matches!(state(&fixture).quote_swap(10_001, true, 0), Err(AmmError::SwapTooLarge)));
+
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+}
+
+#[test]
+fn swap_rejects_an_empty_pool() {
+    let mut fixture = Fixture::with_pool();
+    let user = fixture.user(10_000, 0);
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::NoLiquidityInPool);
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use amm::error::AmmError;
+use amm_client::{ata, instructions, twap_order_pda};
+use anchor_spl::token::spl_token;
+use common::*;
+use litesvm_token::MintTo;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const INTERVAL: i64 = 60;
+
+// pool at 4 y per x, owner escrowing 30_000 x to sell in slices of 10_000
+fn with_order() -> (Fixture, solana_sdk::signature::Keypair) {
+    let mut fixture = Fixture::with_pool();
+    fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    fixture.set_time(1_000);
+
+    let owner = fixture.user(30_000, 0);
+    let ix = instructions::place_twap_order(&owner.pubkey(), &fixture.pool, 1, true, 30_000, 10_000, INTERVAL, 300);
+    fixture.send(ix, &[&owner]).unwrap();
+    (fixture, owner)
+}
+
+#[test]
+fn twap_order_runs_one_slice_per_interval() {
+    let (mut fixture, owner) = with_order();
+    let cranker = fixture.user(0, 0);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+
+    assert_amm_error(fixture.send(crank.clone(), &[&cranker]), AmmError::TwapIntervalNotElapsed);
+
+    for slice in 1..=3u64 {
+        fixture.set_time(1_000 + INTERVAL * slice as i64);
+        fixture.send(crank.clone(), &[&cranker]).unwrap();
+        assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000 + 10_000 * slice);
+
+        if slice < 3 {
+            assert_amm_error(fixture.send(crank.clone(), &[&cranker]), AmmError::TwapIntervalNotElapsed);
+        }
+    }
+
+    assert!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_y) > 0);
+    let order = twap_order_pda(&fixture.pool.config, &owner.pubkey(), 1).0;
+    assert!(fixture.svm.get_account(&order).is_none());
+}
+
+#[test]
+fn twap_slice_rejects_a_price_pushed_away_from_the_average() {
+    let (mut fixture, owner) = with_order();
+
+    // someone dumps x right before the slice, well past the 3% the order allows
+    fixture.set_time(1_000 + INTERVAL);
+    let trader = fixture.user(200_000, 0);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 200_000, true, 0, None, false, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+
+    let cranker = fixture.user(0, 0);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    assert_amm_error(fixture.send(crank, &[&cranker]), AmmError::SlippageExceeded);
+}
+
+#[test]
+fn cancel_twap_order_returns_what_is_left() {
+    let (mut fixture, owner) = with_order();
+    let cranker = fixture.user(0, 0);
+
+    fixture.set_time(1_000 + INTERVAL);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    fixture.send(crank, &[&cranker]).unwrap();
+
+    let ix = instructions::cancel_twap_order(&owner.pubkey(), &fixture.pool, 1, true);
+    fixture.send(ix, &[&owner]).unwrap();
+
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_x), 20_000);
+}
+
+#[test]
+fn last_twap_slice_sells_tokens_sent_to_the_escrow() {
+    let (mut fixture, owner) = with_order();
+    let order = twap_order_pda(&fixture.pool.config, &owner.pubkey(), 1).0;
+    let escrow = ata(&order, &fixture.pool.mint_x);
+
+    // a griefer's dust would otherwise keep the escrow from closing
+    let donor = fixture.user(1, 0);
+    let donor_x = ata(&donor.pubkey(), &fixture.pool.mint_x);
+    let ix = spl_token::instruction::transfer(&spl_token::ID, &donor_x, &escrow, &donor.pubkey(), &[], 1).unwrap();
+    fixture.send(ix, &[&donor]).unwrap();
+
+    let cranker = fixture.user(0, 0);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    for slice in 1..=3 {
+        fixture.set_time(1_000 + INTERVAL * slice);
+        fixture.send(crank.clone(), &[&cranker]).unwrap();
+    }
+
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_030_001);
+    assert!(fixture.svm.get_account(&order).is_none());
+    assert!(fixture.svm.get_account(&escrow).is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn cancel_twap_order_after_close_pool() {
+    let mut fixture = Fixture::with_pool();
+    let owner = fixture.user(30_000, 0);
+    let ix = instructions::place_twap_order(&owner.pubkey(), &fixture.pool, 1, true, 30_000, 10_000, INTERVAL, 300);
+    fixture.send(ix, &[&owner]).unwrap();
+
+    let authority = fixture.authority.pubkey();
+    fixture.send(instructions::close_pool(&authority, &fixture.pool, false), &[]).unwrap();
+
+    let ix = instructions::cancel_twap_order(&owner.pubkey(), &fixture.pool, 1, true);
+    fixture.send(ix, &[&owner]).unwrap();
+    assert_eq!(fixture.balance(&owner.pubkey(), &fixture.pool.mint_x), 30_000);
+}
+
+#[test]
+fn twap_slice_rejects_input_over_max_swap_size() {
+    let (mut fixture, owner) = with_order();
+    // a 10_000 slice is 1% of the x reserve
+    let ix = instructions::set_caps(&fixture.authority.pubkey(), &fixture.pool, 99, 0, 0);
+    fixture.send(ix, &[]).unwrap();
+
+    fixture.set_time(1_000 + INTERVAL);
+    let cranker = fixture.user(0, 0);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    assert_amm_error(fixture.send(crank, &[&cranker]), AmmError::SwapTooLarge);
+}
+
+#[test]
+fn twap_slice_rechecks_the_whitelist() {
+    let (mut fixture, owner) = with_order();
+    let authority = fixture.authority.pubkey();
+    fixture.send(instructions::set_permissioned(&authority, &fixture.pool, true), &[]).unwrap();
+    fixture.set_time(1_000 + INTERVAL);
+
+    let cranker = fixture.user(0, 0);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    assert_amm_error(fixture.send(crank, &[&cranker]), AmmError::NotWhitelisted);
+
+    // the cranker isn't whitelisted, the slice passes the owner's entry
+    fixture.send(instructions::add_to_whitelist(&authority, &fixture.pool, &owner.pubkey()), &[]).unwrap();
+    fixture.pool.permissioned = true;
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    fixture.send(crank, &[&cranker]).unwrap();
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_010_000);
+}
+
+#[test]
+fn twap_slice_rejects_a_pool_that_gained_a_hook() {
+    let (mut fixture, owner) = with_order();
+    let ix = instructions::set_hook(&fixture.authority.pubkey(), &fixture.pool, Some(Pubkey::new_unique()));
+    fixture.send(ix, &[]).unwrap();
+    fixture.set_time(1_000 + INTERVAL);
+
+    let cranker = fixture.user(0, 0);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    assert_amm_error(fixture.send(crank, &[&cranker]), AmmError::HookedPool);
+}
+
+#[test]
+fn twap_slice_rejects_a_window_without_a_price() {
+    let mut fixture = Fixture::with_pool();
+    fixture.set_time(1_000);
+    let owner = fixture.user(30_000, 0);
+    let ix = instructions::place_twap_order(&owner.pubkey(), &fixture.pool, 1, true, 30_000, 10_000, INTERVAL, 300);
+    fixture.send(ix, &[&owner]).unwrap();
+
+    // reserves only show up in the second the slice runs, the oracle never saw a price since the order was placed
+    fixture.set_time(1_000 + INTERVAL);
+    let (vault_x, vault_y) = (fixture.pool.vault_x, fixture.pool.vault_y);
+    let (mint_x, mint_y) = (fixture.pool.mint_x, fixture.pool.mint_y);
+    MintTo::new(&mut fixture.svm, &fixture.authority, &mint_x, &vault_x, 1_000_000).send().unwrap();
+    MintTo::new(&mut fixture.svm, &fixture.authority, &mint_y, &vault_y, 4_000_000).send().unwrap();
+    fixture.send(instructions::sync(&fixture.authority.pubkey(), &fixture.pool), &[]).unwrap();
+
+    let cranker = fixture.user(0, 0);
+    let crank = instructions::execute_twap_slice(&cranker.pubkey(), &fixture.pool, &owner.pubkey(), 1, true);
+    assert_amm_error(fixture.send(crank, &[&cranker]), AmmError::SlippageExceeded);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use amm::error::AmmError;
+use amm_client::instructions;
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+#[test]
+fn lock_and_unlock() {
+    let mut fixture = Fixture::with_pool();
+    let authority = fixture.authority.pubkey();
+    fixture.set_time(5_000);
+
+    fixture.send(instructions::lock(&authority, &fixture.pool), &[]).unwrap();
+    assert!(fixture.config().locked);
+    assert_eq!(fixture.config().locked_at, 5_000);
+
+    fixture.send(instructions::unlock(&authority, &fixture.pool), &[]).unwrap();
+    assert!(!fixture.config().locked);
+    assert_eq!(fixture.config().locked_at, 0);
+}
+
+#[test]
+fn lock_rejects_locked_pool() {
+    let mut fixture = Fixture::with_pool();
+    let authority = fixture.authority.pubkey();
+    fixture.send(instructions::lock(&authority, &fixture.pool), &[]).unwrap();
+
+    assert_amm_error(fixture.send(instructions::lock(&authority, &fixture.pool), &[]), AmmError::PoolLocked);
+}
+
+#[test]
+fn unlock_rejects_unlocked_pool() {
+    let mut fixture = Fixture::with_pool();
+    let authority = fixture.authority.pubkey();
+
+    assert_amm_error(fixture.send(instructions::unlock(&authority, &fixture.pool), &[]), AmmError::PoolUnlocked);
+}
+
+#[test]
+fn lock_rejects_other_signers() {
+    let mut fixture = Fixture::with_pool();
+    let stranger = Keypair::new();
+    let ix = instructions::lock(&stranger.pubkey(), &fixture.pool);
+
+    assert_amm_error(fixture.send(ix, &[&stranger]), AmmError::InvalidAuthority);
+}
+
+#[test]
+fn close_pool_after_everything_is_withdrawn() {
+    let mut fixture = Fixture::with_pool();
+    let authority = fixture.authority.pubkey();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+
+    assert_amm_error(fixture.send(instructions::close_pool(&authority, &fixture.pool, false), &[]), AmmError::PoolNotEmpty);
+
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 2_000_000, 0, 0, None, false, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+    fixture.send(instructions::close_pool(&authority, &fixture.pool, false), &[]).unwrap();
+
+    assert!(fixture.svm.get_account(&fixture.pool.config).is_none_or(|a| a.lamports == 0));
+    assert!(fixture.svm.get_account(&fixture.pool.vault_x).is_none_or(|a| a.lamports == 0));
+    assert!(fixture.svm.get_account(&fixture.pool.vault_y).is_none_or(|a| a.lamports == 0));
+}
+
+#[test]
+fn closed_pool_seed_cannot_be_reused() {
+    let mut fixture = Fixture::with_pool();
+    let authority = fixture.authority.pubkey();
+    fixture.send(instructions::close_pool(&authority, &fixture.pool, false), &[]).unwrap();
+
+    // the lp mint outlives the pool, so its init fails
+    let ix = fixture.initialize_ix(Some(authority), 0, None);
+    assert!(fixture.send(ix, &[]).is_err());
+}
+
+#[test]
+fn close_pool_requires_an_authority() {
+    let mut fixture = Fixture::new();
+    let ix = fixture.initialize_ix(None, 0, None);
+    fixture.send(ix, &[]).unwrap();
+
+    let ix = instructions::close_pool(&fixture.authority.pubkey(), &fixture.pool, false);
+    assert_amm_error(fixture.send(ix, &[]), AmmError::NoAuthoritySet);
+}
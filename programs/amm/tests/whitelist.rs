@@ -0,0 +1,85 @@
+mod common;
+
+use amm::error::AmmError;
+use amm_client::{instructions, whitelist_pda};
+use common::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+// a permissioned pool holding liquidity from an lp that was never whitelisted
+fn permissioned() -> (Fixture, Keypair) {
+    let mut fixture = Fixture::with_pool();
+    let lp = fixture.seeded_lp(1_000_000, 4_000_000, 2_000_000);
+    let ix = instructions::set_permissioned(&fixture.authority.pubkey(), &fixture.pool, true);
+    fixture.send(ix, &[]).unwrap();
+    (fixture, lp)
+}
+
+#[test]
+fn deposit_on_permissioned_pool_requires_whitelist() {
+    let (mut fixture, _) = permissioned();
+    let user = fixture.user(1_000, 4_000);
+
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 2_000, 1_000, 4_000, None, false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::NotWhitelisted);
+
+    let ix = instructions::add_to_whitelist(&fixture.authority.pubkey(), &fixture.pool, &user.pubkey());
+    fixture.send(ix, &[]).unwrap();
+    fixture.pool.permissioned = true;
+
+    let ix = instructions::deposit(&user.pubkey(), &fixture.pool, 2_000, 1_000, 4_000, None, false, &[]);
+    fixture.send(ix, &[&user]).unwrap();
+    assert_eq!(fixture.balance(&user.pubkey(), &fixture.pool.mint_lp), 2_000);
+}
+
+#[test]
+fn withdraw_on_permissioned_pool_requires_whitelist() {
+    let (mut fixture, lp) = permissioned();
+
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 1_000_000, 0, 0, None, false, true, &[]);
+    assert_amm_error(fixture.send(ix, &[&lp]), AmmError::NotWhitelisted);
+
+    let ix = instructions::add_to_whitelist(&fixture.authority.pubkey(), &fixture.pool, &lp.pubkey());
+    fixture.send(ix, &[]).unwrap();
+    fixture.pool.permissioned = true;
+
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 1_000_000, 0, 0, None, false, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_x), 500_000);
+}
+
+#[test]
+fn remove_from_whitelist_revokes_access() {
+    let (mut fixture, _) = permissioned();
+    let authority = fixture.authority.pubkey();
+    let user = fixture.user(20_000, 0);
+
+    let ix = instructions::add_to_whitelist(&authority, &fixture.pool, &user.pubkey());
+    fixture.send(ix, &[]).unwrap();
+    let entry = whitelist_pda(&fixture.pool.config, &user.pubkey()).0;
+    let rent = fixture.svm.get_account(&entry).unwrap().lamports;
+    let before = fixture.svm.get_account(&authority).unwrap().lamports;
+
+    // the entry is closed and its rent goes back to the authority, less the fee for this transaction
+    let ix = instructions::remove_from_whitelist(&authority, &fixture.pool, &user.pubkey());
+    fixture.send(ix, &[]).unwrap();
+    assert!(fixture.svm.get_account(&entry).is_none_or(|account| account.lamports == 0));
+    assert!(fixture.svm.get_account(&authority).unwrap().lamports > before + rent - 10_000);
+
+    // without an entry to pass, the user is back to being rejected
+    let ix = instructions::swap(&user.pubkey(), &fixture.pool, 10_000, true, 0, None, false, &[]);
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::NotWhitelisted);
+}
+
+#[test]
+fn whitelist_is_authority_only() {
+    let (mut fixture, _) = permissioned();
+    let user = fixture.wallet();
+
+    let ix = instructions::add_to_whitelist(&user.pubkey(), &fixture.pool, &user.pubkey());
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidAuthority);
+
+    let ix = instructions::add_to_whitelist(&fixture.authority.pubkey(), &fixture.pool, &user.pubkey());
+    fixture.send(ix, &[]).unwrap();
+    let ix = instructions::remove_from_whitelist(&user.pubkey(), &fixture.pool, &user.pubkey());
+    assert_amm_error(fixture.send(ix, &[&user]), AmmError::InvalidAuthority);
+}
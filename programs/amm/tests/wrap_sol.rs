@@ -0,0 +1,122 @@
+mod common;
+
+use amm::error::AmmError;
+use amm_client::{ata, instructions, position_pda, wsol_pda, PoolState};
+use anchor_spl::token::spl_token::{self, native_mint};
+use common::*;
+use solana_sdk::{
+    instruction::AccountMeta,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+// a wSOL / y pool holding 1 SOL and 4 y, deposited in SOL
+fn seeded() -> (Fixture, Keypair) {
+    let mut fixture = Fixture::native();
+    let lp = fixture.wallet();
+    let mint_y = fixture.pool.mint_y;
+    fixture.fund(&lp.pubkey(), &mint_y, 4_000_000);
+
+    let ix = instructions::deposit(&lp.pubkey(), &fixture.pool, 2_000_000, 1_000_000, 4_000_000, None, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+    (fixture, lp)
+}
+
+fn state(fixture: &Fixture) -> PoolState {
+    PoolState::new(&fixture.config(), fixture.mint(&fixture.pool.mint_lp).supply)
+}
+
+// lamports that went into accounts the user paid rent for, the fixture's authority pays the fees
+fn rent(fixture: &Fixture, accounts: &[Pubkey], before: &[u64]) -> u64 {
+    accounts.iter().zip(before).map(|(account, before)| fixture.lamports(account) - before).sum()
+}
+
+#[test]
+fn deposit_with_wrap_sol_pays_in_sol() {
+    let mut fixture = Fixture::native();
+    let lp = fixture.wallet();
+    let mint_y = fixture.pool.mint_y;
+    fixture.fund(&lp.pubkey(), &mint_y, 4_000_000);
+
+    let created = [ata(&lp.pubkey(), &native_mint::ID), ata(&lp.pubkey(), &fixture.pool.mint_lp), position_pda(&fixture.pool.config, &lp.pubkey()).0];
+    let before = fixture.lamports(&lp.pubkey());
+    let ix = instructions::deposit(&lp.pubkey(), &fixture.pool, 2_000_000, 1_000_000, 4_000_000, None, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+
+    assert_eq!(before - fixture.lamports(&lp.pubkey()), 1_000_000 + rent(&fixture, &created, &[0; 3]));
+    assert_eq!(fixture.token_account(&fixture.pool.vault_x).amount, 1_000_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_lp), 2_000_000);
+    // the temporary account is gone again
+    assert_eq!(fixture.lamports(&wsol_pda(&lp.pubkey()).0), 0);
+}
+
+#[test]
+fn swap_with_wrap_sol_buys_and_sells_sol() {
+    let (mut fixture, _) = seeded();
+    let trader = fixture.wallet();
+    let mint_y = fixture.pool.mint_y;
+    fixture.fund(&trader.pubkey(), &mint_y, 100_000);
+    let native_ata = [ata(&trader.pubkey(), &native_mint::ID)];
+
+    // buying sol with y
+    let quote = state(&fixture).quote_swap(100_000, false, 0).unwrap();
+    let (before, ata_before) = (fixture.lamports(&trader.pubkey()), [fixture.lamports(&native_ata[0])]);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 100_000, false, 0, None, true, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+    assert_eq!(fixture.lamports(&trader.pubkey()) + rent(&fixture, &native_ata, &ata_before), before + quote.amount_out);
+    assert_eq!(fixture.balance(&trader.pubkey(), &mint_y), 0);
+
+    // selling sol for y
+    let quote = state(&fixture).quote_swap(10_000, true, 0).unwrap();
+    let (before, ata_before) = (fixture.lamports(&trader.pubkey()), [fixture.lamports(&native_ata[0])]);
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 10_000, true, 0, None, true, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+    assert_eq!(before - fixture.lamports(&trader.pubkey()), quote.amount_in + rent(&fixture, &native_ata, &ata_before));
+    assert_eq!(fixture.balance(&trader.pubkey(), &mint_y), quote.amount_out);
+    assert_eq!(fixture.lamports(&wsol_pda(&trader.pubkey()).0), 0);
+}
+
+#[test]
+fn withdraw_with_wrap_sol_pays_out_sol() {
+    let (mut fixture, lp) = seeded();
+    let native_ata = [ata(&lp.pubkey(), &native_mint::ID)];
+
+    let (before, ata_before) = (fixture.lamports(&lp.pubkey()), [fixture.lamports(&native_ata[0])]);
+    let ix = instructions::withdraw(&lp.pubkey(), &fixture.pool, 1_000_000, 0, 0, None, true, true, &[]);
+    fixture.send(ix, &[&lp]).unwrap();
+
+    assert_eq!(fixture.lamports(&lp.pubkey()) + rent(&fixture, &native_ata, &ata_before), before + 500_000);
+    assert_eq!(fixture.balance(&lp.pubkey(), &fixture.pool.mint_y), 2_000_000);
+}
+
+#[test]
+fn wrap_sol_leaves_the_users_wsol_account_alone() {
+    let (mut fixture, _) = seeded();
+    let trader = fixture.wallet();
+    let mint_y = fixture.pool.mint_y;
+    fixture.fund(&trader.pubkey(), &mint_y, 100_000);
+
+    // wSOL the trader already holds
+    fixture.fund(&trader.pubkey(), &native_mint::ID, 0);
+    let wsol = ata(&trader.pubkey(), &native_mint::ID);
+    fixture.send(system_instruction::transfer(&trader.pubkey(), &wsol, 500_000), &[&trader]).unwrap();
+    fixture.send(spl_token::instruction::sync_native(&spl_token::ID, &wsol).unwrap(), &[]).unwrap();
+
+    let ix = instructions::swap(&trader.pubkey(), &fixture.pool, 100_000, false, 0, None, true, &[]);
+    fixture.send(ix, &[&trader]).unwrap();
+
+    assert_eq!(fixture.token_account(&wsol).amount, 500_000);
+}
+
+#[test]
+fn wrap_sol_needs_the_temporary_account() {
+    let (mut fixture, _) = seeded();
+    let trader = fixture.wallet();
+    let wsol = wsol_pda(&trader.pubkey()).0;
+
+    let mut ix = instructions::swap(&trader.pubkey(), &fixture.pool, 10_000, true, 0, None, true, &[]);
+    let slot = ix.accounts.iter().position(|meta| meta.pubkey == wsol).unwrap();
+    ix.accounts[slot] = AccountMeta::new_readonly(amm::ID, false);
+    assert_amm_error(fixture.send(ix, &[&trader]), AmmError::MissingWsolAccount);
+}